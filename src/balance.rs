@@ -0,0 +1,457 @@
+use crate::cards::{Card, Rarity};
+use crate::components::{Health, Inventory, ProjectileKind, Stats};
+use crate::modifiers::BaseStats;
+use crate::resources::BalanceHandle;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// Base values every player starts a match with, before any cards are applied.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerBalance {
+    pub max_health: f32,
+    pub move_speed: f32,
+    pub jump_force: f32,
+    pub damage: f32,
+    pub projectile_speed: f32,
+    pub shot_cooldown: f32,
+    pub air_jumps: f32,
+    pub dash_speed: f32,
+    pub dash_cooldown: f32,
+    pub block_duration: f32,
+    pub block_cooldown: f32,
+    pub knockback: f32,
+    pub projectile_count: f32,
+    /// Degrees.
+    pub spread: f32,
+    pub burst_count: f32,
+    pub burst_interval: f32,
+    /// Degrees.
+    pub jitter: f32,
+}
+
+/// How a kind of projectile flies and how much speed it keeps when it bounces.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectilePhysics {
+    pub restitution: f32,
+    pub gravity_scale: f32,
+}
+
+/// How explosive projectiles hurt and push the players around them.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExplosionBalance {
+    /// Share of the damage lost at the edge of the blast.
+    pub falloff: f32,
+    /// Speed a player is thrown at by a blast right on top of them.
+    pub knockback: f32,
+    /// Share of the damage an explosion deals to the player who fired it. Zero spares them.
+    pub owner_damage: f32,
+}
+
+/// How stuns and freezes wear down, and how slows turn into a freeze.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CrowdControlBalance {
+    /// Share of its duration each stun or freeze keeps for every one landed before it since
+    /// the diminishing returns last wore off.
+    pub diminishing: f32,
+    /// Seconds after the last stun or freeze before durations are back to full.
+    pub diminishing_reset: f32,
+    /// Stuns and freezes after which a player ignores more until the diminishing returns reset.
+    pub immune_after: u32,
+    /// Slows landed on a fully stacked slow that freeze the player.
+    pub freeze_buildup: u32,
+    pub freeze_duration: f32,
+    /// Damage dealt when a freeze built up from slows wears off.
+    pub shatter_damage: f32,
+}
+
+/// Draw weight of each card of a rarity. Only the ratios matter.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RarityWeights {
+    pub common: f32,
+    pub uncommon: f32,
+    pub rare: f32,
+    pub legendary: f32,
+}
+
+impl RarityWeights {
+    pub fn get(&self, rarity: Rarity) -> f32 {
+        match rarity {
+            Rarity::Common => self.common,
+            Rarity::Uncommon => self.uncommon,
+            Rarity::Rare => self.rare,
+            Rarity::Legendary => self.legendary,
+        }
+    }
+}
+
+/// How the cards offered between rounds are drawn.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardDrawBalance {
+    pub weights: RarityWeights,
+    /// Each entry promises one card of that rarity or better in every offer.
+    #[serde(default)]
+    pub guaranteed: Vec<Rarity>,
+}
+
+/// Tuning values loaded from `assets/game.balance.ron`.
+///
+/// The latest loaded values are also kept as a resource so gameplay systems can read them
+/// without going through `Assets<Balance>`.
+#[derive(Resource, Clone, Debug, PartialEq, Deserialize, TypeUuid, TypePath)]
+#[uuid = "ce1062ed-4c3d-422b-8260-d7180e4b3a92"]
+#[serde(deny_unknown_fields)]
+pub struct Balance {
+    pub player: PlayerBalance,
+    pub projectile_lifetime: f32,
+    /// Seconds after leaving the ground during which a jump still counts as a ground jump.
+    pub coyote_time: f32,
+    /// Seconds a jump press is remembered while the player cannot jump yet.
+    pub jump_buffer: f32,
+    pub dash_duration: f32,
+    /// Seconds a dash or blink keeps the player from being hit.
+    pub dash_invulnerability: f32,
+    /// Plain shots, which break on the first terrain hit.
+    pub bolt: ProjectilePhysics,
+    /// Shots that bounce off terrain.
+    pub bouncer: ProjectilePhysics,
+    /// Share of its damage a piercing projectile keeps after each player it goes through.
+    pub pierce_falloff: f32,
+    pub explosion: ExplosionBalance,
+    /// Seconds a knocked back player has no horizontal control.
+    pub hit_stun: f32,
+    /// Share of a shooter's lifesteal that also applies to the poison and burn damage of their
+    /// status effects. Zero leaves damage over time out of it.
+    pub poison_lifesteal: f32,
+    pub crowd_control: CrowdControlBalance,
+    /// Seconds a burn or poison from a hazard keeps going after the player leaves it.
+    pub hazard_linger: f32,
+    pub card_draw: CardDrawBalance,
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        Self {
+            player: PlayerBalance {
+                max_health: 100.0,
+                move_speed: 200.0,
+                // About 100 px of height under the default gravity, enough to reach the centre platform
+                jump_force: 140.0,
+                damage: 10.0,
+                projectile_speed: 300.0,
+                shot_cooldown: 0.5,
+                air_jumps: 0.0,
+                dash_speed: 600.0,
+                dash_cooldown: 1.0,
+                block_duration: 0.3,
+                block_cooldown: 3.0,
+                knockback: 80.0,
+                projectile_count: 1.0,
+                spread: 15.0,
+                burst_count: 1.0,
+                burst_interval: 0.1,
+                jitter: 0.0,
+            },
+            projectile_lifetime: 2.0,
+            coyote_time: 0.1,
+            jump_buffer: 0.1,
+            dash_duration: 0.15,
+            dash_invulnerability: 0.2,
+            bolt: ProjectilePhysics {
+                restitution: 0.0,
+                gravity_scale: 1.0,
+            },
+            bouncer: ProjectilePhysics {
+                restitution: 0.9,
+                gravity_scale: 1.0,
+            },
+            pierce_falloff: 0.75,
+            explosion: ExplosionBalance {
+                falloff: 0.5,
+                knockback: 250.0,
+                owner_damage: 0.0,
+            },
+            hit_stun: 0.2,
+            poison_lifesteal: 0.0,
+            crowd_control: CrowdControlBalance {
+                diminishing: 0.5,
+                diminishing_reset: 4.0,
+                immune_after: 3,
+                freeze_buildup: 2,
+                freeze_duration: 1.0,
+                shatter_damage: 15.0,
+            },
+            hazard_linger: 0.5,
+            card_draw: CardDrawBalance {
+                weights: RarityWeights {
+                    common: 60.0,
+                    uncommon: 25.0,
+                    rare: 12.0,
+                    legendary: 3.0,
+                },
+                guaranteed: vec![Rarity::Uncommon],
+            },
+        }
+    }
+}
+
+impl Balance {
+    fn fields(&self) -> [(&'static str, f32); 43] {
+        [
+            ("player.max_health", self.player.max_health),
+            ("player.move_speed", self.player.move_speed),
+            ("player.jump_force", self.player.jump_force),
+            ("player.damage", self.player.damage),
+            ("player.projectile_speed", self.player.projectile_speed),
+            ("player.shot_cooldown", self.player.shot_cooldown),
+            ("player.air_jumps", self.player.air_jumps),
+            ("player.dash_speed", self.player.dash_speed),
+            ("player.dash_cooldown", self.player.dash_cooldown),
+            ("player.block_duration", self.player.block_duration),
+            ("player.block_cooldown", self.player.block_cooldown),
+            ("player.knockback", self.player.knockback),
+            ("player.projectile_count", self.player.projectile_count),
+            ("player.spread", self.player.spread),
+            ("player.burst_count", self.player.burst_count),
+            ("player.burst_interval", self.player.burst_interval),
+            ("player.jitter", self.player.jitter),
+            ("projectile_lifetime", self.projectile_lifetime),
+            ("coyote_time", self.coyote_time),
+            ("jump_buffer", self.jump_buffer),
+            ("dash_duration", self.dash_duration),
+            ("dash_invulnerability", self.dash_invulnerability),
+            ("bolt.restitution", self.bolt.restitution),
+            ("bolt.gravity_scale", self.bolt.gravity_scale),
+            ("bouncer.restitution", self.bouncer.restitution),
+            ("bouncer.gravity_scale", self.bouncer.gravity_scale),
+            ("pierce_falloff", self.pierce_falloff),
+            ("explosion.falloff", self.explosion.falloff),
+            ("explosion.knockback", self.explosion.knockback),
+            ("explosion.owner_damage", self.explosion.owner_damage),
+            ("hit_stun", self.hit_stun),
+            ("poison_lifesteal", self.poison_lifesteal),
+            ("crowd_control.diminishing", self.crowd_control.diminishing),
+            (
+                "crowd_control.diminishing_reset",
+                self.crowd_control.diminishing_reset,
+            ),
+            (
+                "crowd_control.immune_after",
+                self.crowd_control.immune_after as f32,
+            ),
+            (
+                "crowd_control.freeze_buildup",
+                self.crowd_control.freeze_buildup as f32,
+            ),
+            (
+                "crowd_control.freeze_duration",
+                self.crowd_control.freeze_duration,
+            ),
+            (
+                "crowd_control.shatter_damage",
+                self.crowd_control.shatter_damage,
+            ),
+            ("hazard_linger", self.hazard_linger),
+            ("card_draw.weights.common", self.card_draw.weights.common),
+            (
+                "card_draw.weights.uncommon",
+                self.card_draw.weights.uncommon,
+            ),
+            ("card_draw.weights.rare", self.card_draw.weights.rare),
+            (
+                "card_draw.weights.legendary",
+                self.card_draw.weights.legendary,
+            ),
+        ]
+    }
+
+    /// Stats for a player that has not picked any cards yet.
+    pub fn base_stats(&self) -> Stats {
+        Stats {
+            move_speed: self.player.move_speed,
+            jump_force: self.player.jump_force,
+            damage: self.player.damage,
+            projectile_speed: self.player.projectile_speed,
+            shot_cooldown: self.player.shot_cooldown,
+            cooldown_timer: 0.0,
+            air_jumps: self.player.air_jumps,
+            dash_speed: self.player.dash_speed,
+            dash_cooldown: self.player.dash_cooldown,
+            dash_timer: 0.0,
+            blink_distance: 0.0,
+            block_duration: self.player.block_duration,
+            block_cooldown: self.player.block_cooldown,
+            block_timer: 0.0,
+            reflect: 0.0,
+            bounces: 0.0,
+            projectile_count: self.player.projectile_count,
+            spread: self.player.spread,
+            burst_count: self.player.burst_count,
+            burst_interval: self.player.burst_interval,
+            jitter: self.player.jitter,
+            homing_turn_rate: 0.0,
+            homing_radius: 0.0,
+            pierce: 0.0,
+            explosion_radius: 0.0,
+            knockback: self.player.knockback,
+            lifesteal: 0.0,
+            on_hit: Vec::new(),
+            on_impact: Vec::new(),
+            aim_angle: 0.0,
+        }
+    }
+
+    pub fn projectile_physics(&self, kind: ProjectileKind) -> &ProjectilePhysics {
+        match kind {
+            ProjectileKind::Bolt => &self.bolt,
+            ProjectileKind::Bouncer => &self.bouncer,
+        }
+    }
+
+    /// Human readable list of every value that differs from `previous`.
+    pub fn describe_changes(&self, previous: &Balance) -> Vec<String> {
+        let mut changes: Vec<String> = previous
+            .fields()
+            .into_iter()
+            .zip(self.fields())
+            .filter(|((_, old), (_, new))| old != new)
+            .map(|((name, old), (_, new))| format!("{name}: {old} -> {new}"))
+            .collect();
+        if self.card_draw.guaranteed != previous.card_draw.guaranteed {
+            changes.push(format!(
+                "card_draw.guaranteed: {:?} -> {:?}",
+                previous.card_draw.guaranteed, self.card_draw.guaranteed
+            ));
+        }
+        changes
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BalanceLoadError {
+    #[error("could not parse balance file {path:?}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: ron::error::SpannedError,
+    },
+    #[error("balance file {path:?}: field `{field}` {reason}")]
+    Invalid {
+        path: PathBuf,
+        field: &'static str,
+        reason: &'static str,
+    },
+}
+
+/// Fields that are shares of something, and so cannot go above 1.
+const FRACTIONS: [&str; 4] = [
+    "pierce_falloff",
+    "explosion.falloff",
+    "explosion.owner_damage",
+    "crowd_control.diminishing",
+];
+
+/// Fields that other values are divided by, and so cannot be zero.
+const POSITIVE: [&str; 1] = ["player.max_health"];
+
+#[derive(Default)]
+pub struct BalanceLoader;
+
+impl AssetLoader for BalanceLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let path = load_context.path().to_path_buf();
+            let balance: Balance =
+                ron::de::from_bytes(bytes).map_err(|source| BalanceLoadError::Parse {
+                    path: path.clone(),
+                    source,
+                })?;
+            for (field, value) in balance.fields() {
+                let reason = if !value.is_finite() || value < 0.0 {
+                    "must be a finite, non-negative number"
+                } else if POSITIVE.contains(&field) && value == 0.0 {
+                    "must be above 0"
+                } else if FRACTIONS.contains(&field) && value > 1.0 {
+                    "must be between 0 and 1"
+                } else {
+                    continue;
+                };
+                return Err(BalanceLoadError::Invalid {
+                    path,
+                    field,
+                    reason,
+                }
+                .into());
+            }
+            load_context.set_default_asset(LoadedAsset::new(balance));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["balance.ron"]
+    }
+}
+
+/// Re-applies balance and card data to live players whenever the files change on disk. Their
+/// stats are recomputed from the new base stats and cards on the next tick of play.
+pub fn hot_reload_system(
+    mut balance_events: EventReader<AssetEvent<Balance>>,
+    mut card_events: EventReader<AssetEvent<Card>>,
+    balances: Res<Assets<Balance>>,
+    balance_handle: Res<BalanceHandle>,
+    cards: Res<Assets<Card>>,
+    mut balance: ResMut<Balance>,
+    mut players: Query<(&mut BaseStats, &mut Health, &mut Inventory)>,
+) {
+    let mut changed = false;
+    for event in balance_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            if *handle != balance_handle.0 {
+                continue;
+            }
+            let Some(loaded) = balances.get(handle) else {
+                continue;
+            };
+            let changes = loaded.describe_changes(&balance);
+            if !changes.is_empty() {
+                info!("Balance reloaded: {}", changes.join(", "));
+                *balance = loaded.clone();
+                changed = true;
+            }
+        }
+    }
+    for event in card_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            if let Some(card) = cards.get(handle) {
+                info!("Card reloaded: {}", card.name);
+                changed = true;
+            }
+        }
+    }
+    if !changed {
+        return;
+    }
+
+    for (mut base, mut health, mut inventory) in players.iter_mut() {
+        base.0 = balance.base_stats();
+        // The cards may be the ones that changed.
+        inventory.set_changed();
+        if health.max != balance.player.max_health {
+            health.current = health.current / health.max * balance.player.max_health;
+            health.max = balance.player.max_health;
+        }
+    }
+}
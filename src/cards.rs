@@ -1,63 +1,592 @@
-use crate::components::Stats;
+use crate::balance::CardDrawBalance;
+use crate::components::{Inventory, Stat};
+use crate::hazard::{HazardKind, HazardSpec};
+use crate::resources::CardLibrary;
+use crate::status::StatusSpec;
+use bevy::asset::{AssetLoader, LoadContext, LoadState, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
 use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use std::path::PathBuf;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub enum CardId {
-    Power,
-    Speed,
-    Jump,
-    Poison,
-    Slow,
+/// How rare a card is, from most to least common.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Legendary,
 }
 
-#[derive(Clone, Copy)]
-pub struct Card {
-    pub id: CardId,
-    pub name: &'static str,
-    pub description: &'static str,
+impl Rarity {
+    /// Colour the card picker uses for cards of this rarity.
+    pub fn color(self) -> Color {
+        match self {
+            Self::Common => Color::rgb(0.75, 0.75, 0.75),
+            Self::Uncommon => Color::rgb(0.3, 0.85, 0.3),
+            Self::Rare => Color::rgb(0.3, 0.55, 1.0),
+            Self::Legendary => Color::rgb(1.0, 0.6, 0.1),
+        }
+    }
 }
 
-pub const ALL_CARDS: &[Card] = &[
-    Card {
-        id: CardId::Power,
-        name: "Power",
-        description: "Increase damage",
-    },
-    Card {
-        id: CardId::Speed,
-        name: "Speed",
-        description: "Increase move speed",
-    },
-    Card {
-        id: CardId::Jump,
-        name: "Jump",
-        description: "Increase jump force",
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ModifierOp {
+    Add,
+    Mul,
+    Set,
+    /// Raises the stat to at least `value`.
+    Max,
+    /// Caps the stat at `value`.
+    Min,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardModifier {
+    pub stat: Stat,
+    pub op: ModifierOp,
+    pub value: f32,
+}
+
+/// Something a card does each time its holder blocks a projectile.
+#[derive(Clone, Debug, Deserialize)]
+pub enum BlockEffect {
+    /// Restores this much health, up to the maximum.
+    Heal(f32),
+    /// Slows every opponent within `radius` of the blocked projectile.
+    SlowNearby {
+        radius: f32,
+        amount: f32,
+        duration: f32,
     },
-    Card {
-        id: CardId::Poison,
-        name: "Poison",
-        description: "Projectiles apply poison",
+}
+
+/// A card definition loaded from an `assets/cards/*.card.ron` file.
+#[derive(Clone, Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "a21064c6-0bf1-4db6-9d8b-7812cd586fc4"]
+#[serde(deny_unknown_fields)]
+pub struct Card {
+    pub name: String,
+    pub description: String,
+    pub rarity: Rarity,
+    pub modifiers: Vec<CardModifier>,
+    #[serde(default)]
+    pub on_block: Vec<BlockEffect>,
+    /// Status effects the holder's projectiles apply on hit.
+    #[serde(default)]
+    pub on_hit: Vec<StatusSpec>,
+    /// Hazards the holder's projectiles leave where they break.
+    #[serde(default)]
+    pub on_impact: Vec<HazardSpec>,
+    /// Most copies of this card a player can hold. Unlimited when left out.
+    #[serde(default)]
+    pub max_stacks: Option<u32>,
+    /// Names of the cards a player must hold before this one is offered to them.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Names of the cards this one cannot be held alongside, whichever was picked first.
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    /// Whether the card is offered to a player who is ahead in the match.
+    #[serde(default = "default_offer_to_leader")]
+    pub offer_to_leader: bool,
+}
+
+fn default_offer_to_leader() -> bool {
+    true
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CardLoadError {
+    #[error("could not parse card file {path:?}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: ron::error::SpannedError,
     },
-    Card {
-        id: CardId::Slow,
-        name: "Frost",
-        description: "Projectiles slow enemies",
+    #[error("card {card:?} in {path:?}: field `{field}` {reason}")]
+    Invalid {
+        path: PathBuf,
+        card: String,
+        field: String,
+        reason: String,
     },
-];
+}
+
+impl Card {
+    /// Whether a player holding `held` may be offered this card: they have room for another
+    /// copy, hold every card it requires and nothing it excludes or that excludes it.
+    pub fn can_join(&self, held: &[&Card]) -> bool {
+        let copies = held.iter().filter(|card| card.name == self.name).count();
+        self.max_stacks.is_none_or(|max| copies < max as usize)
+            && self
+                .requires
+                .iter()
+                .all(|name| held.iter().any(|card| &card.name == name))
+            && !held.iter().any(|card| {
+                self.excludes.contains(&card.name) || card.excludes.contains(&self.name)
+            })
+    }
 
-pub fn random_choices(n: usize) -> Vec<Card> {
-    let mut cards = ALL_CARDS.to_vec();
+    fn validate(&self, path: &std::path::Path) -> Result<(), CardLoadError> {
+        let invalid = |field: String, reason: &str| CardLoadError::Invalid {
+            path: path.to_path_buf(),
+            card: self.name.clone(),
+            field,
+            reason: reason.to_string(),
+        };
+        if self.name.trim().is_empty() {
+            return Err(invalid("name".into(), "must not be empty"));
+        }
+        if self.max_stacks == Some(0) {
+            return Err(invalid("max_stacks".into(), "must be at least 1"));
+        }
+        for (field, names) in [("requires", &self.requires), ("excludes", &self.excludes)] {
+            if let Some(i) = names.iter().position(|name| *name == self.name) {
+                return Err(invalid(
+                    format!("{field}[{i}]"),
+                    "must not name the card itself",
+                ));
+            }
+        }
+        for (i, modifier) in self.modifiers.iter().enumerate() {
+            if !modifier.value.is_finite() {
+                return Err(invalid(
+                    format!("modifiers[{i}].value"),
+                    "must be a finite number",
+                ));
+            }
+            if modifier.op == ModifierOp::Mul && modifier.value < 0.0 {
+                return Err(invalid(
+                    format!("modifiers[{i}].value"),
+                    "must not be negative for a Mul modifier",
+                ));
+            }
+        }
+        for (i, effect) in self.on_block.iter().enumerate() {
+            let values = match *effect {
+                BlockEffect::Heal(amount) => vec![("amount", amount)],
+                BlockEffect::SlowNearby {
+                    radius,
+                    amount,
+                    duration,
+                } => {
+                    if !(0.0..=1.0).contains(&amount) {
+                        return Err(invalid(
+                            format!("on_block[{i}].amount"),
+                            "must be between 0 and 1",
+                        ));
+                    }
+                    vec![("radius", radius), ("duration", duration)]
+                }
+            };
+            if let Some((name, _)) = values
+                .into_iter()
+                .find(|(_, value)| !value.is_finite() || *value < 0.0)
+            {
+                return Err(invalid(
+                    format!("on_block[{i}].{name}"),
+                    "must be a finite, non-negative number",
+                ));
+            }
+        }
+        for (i, effect) in self.on_hit.iter().enumerate() {
+            if let Some((name, _)) = [
+                ("magnitude", effect.magnitude),
+                ("duration", effect.duration),
+            ]
+            .into_iter()
+            .find(|(_, value)| !value.is_finite() || *value < 0.0)
+            {
+                return Err(invalid(
+                    format!("on_hit[{i}].{name}"),
+                    "must be a finite, non-negative number",
+                ));
+            }
+            if effect.kind.is_fraction() && effect.magnitude > 1.0 {
+                return Err(invalid(
+                    format!("on_hit[{i}].magnitude"),
+                    "must be between 0 and 1",
+                ));
+            }
+            if effect.max_stacks == 0 {
+                return Err(invalid(
+                    format!("on_hit[{i}].max_stacks"),
+                    "must be at least 1",
+                ));
+            }
+        }
+        for (i, hazard) in self.on_impact.iter().enumerate() {
+            if let Some((name, _)) = [
+                ("radius", hazard.radius),
+                ("duration", hazard.duration),
+                ("magnitude", hazard.magnitude),
+            ]
+            .into_iter()
+            .find(|(_, value)| !value.is_finite() || *value < 0.0)
+            {
+                return Err(invalid(
+                    format!("on_impact[{i}].{name}"),
+                    "must be a finite, non-negative number",
+                ));
+            }
+            if hazard.kind == HazardKind::Ice && hazard.magnitude > 1.0 {
+                return Err(invalid(
+                    format!("on_impact[{i}].magnitude"),
+                    "must be between 0 and 1",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct CardLoader;
+
+impl AssetLoader for CardLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let path = load_context.path().to_path_buf();
+            let card: Card = ron::de::from_bytes(bytes).map_err(|source| CardLoadError::Parse {
+                path: path.clone(),
+                source,
+            })?;
+            card.validate(&path)?;
+            load_context.set_default_asset(LoadedAsset::new(card));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["card.ron"]
+    }
+}
+
+/// Warns about `requires` and `excludes` entries that name no card in the library, once the
+/// whole library has loaded and again whenever a card file changes.
+pub fn check_card_names(
+    mut events: EventReader<AssetEvent<Card>>,
+    asset_server: Res<AssetServer>,
+    library: Option<Res<CardLibrary>>,
+    cards: Res<Assets<Card>>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+    let Some(library) = library else {
+        return;
+    };
+    let ids = library.handles.iter().map(|handle| handle.id());
+    if !matches!(
+        asset_server.get_group_load_state(ids),
+        LoadState::Loaded | LoadState::Failed
+    ) {
+        return;
+    }
+    let loaded: Vec<&Card> = library
+        .handles
+        .iter()
+        .filter_map(|handle| cards.get(&cards.get_handle(handle.id())))
+        .collect();
+    for card in &loaded {
+        for (field, names) in [("requires", &card.requires), ("excludes", &card.excludes)] {
+            for name in names
+                .iter()
+                .filter(|name| !loaded.iter().any(|other| other.name == **name))
+            {
+                warn!(
+                    "Card {:?}: `{field}` names {name:?}, which matches no card in the library",
+                    card.name
+                );
+            }
+        }
+    }
+}
+
+/// Draws up to `n` distinct cards from the library for a player holding `inventory`, skipping
+/// any that failed to load or that the player may not take (see [`Card::can_join`]). Each card
+/// is drawn with the weight of its rarity, and every rarity in `draw.guaranteed` first claims a
+/// slot for a card of that rarity or better while one is left. Cards kept from a `leading`
+/// player are only offered when nothing else is left, and the offer comes up short once even
+/// those run out.
+pub fn random_choices(
+    n: usize,
+    library: &CardLibrary,
+    cards: &Assets<Card>,
+    draw: &CardDrawBalance,
+    inventory: &Inventory,
+    leading: bool,
+) -> Vec<Handle<Card>> {
+    let held: Vec<&Card> = inventory
+        .cards
+        .iter()
+        .filter_map(|handle| cards.get(handle))
+        .collect();
+    // The first pool is drawn from before the second, which holds the cards the leader
+    // should not normally see.
+    let mut pools: [Vec<(Handle<Card>, Rarity)>; 2] = Default::default();
+    for handle in library
+        .handles
+        .iter()
+        .map(|handle| cards.get_handle(handle.id()))
+    {
+        let Some(card) = cards.get(&handle).filter(|card| card.can_join(&held)) else {
+            continue;
+        };
+        let pool = usize::from(leading && !card.offer_to_leader);
+        pools[pool].push((handle, card.rarity));
+    }
     let mut rng = rand::thread_rng();
-    cards.shuffle(&mut rng);
-    cards.into_iter().take(n).collect()
+    let mut offer = Vec::new();
+    for &minimum in draw.guaranteed.iter().take(n) {
+        if let Some(handle) = take_weighted(&mut pools, draw, &mut rng, |rarity| rarity >= minimum)
+        {
+            offer.push(handle);
+        }
+    }
+    while offer.len() < n {
+        let Some(handle) = take_weighted(&mut pools, draw, &mut rng, |_| true) else {
+            break;
+        };
+        offer.push(handle);
+    }
+    // Keep the guaranteed cards from always taking the first slots.
+    offer.shuffle(&mut rng);
+    offer
 }
 
-pub fn apply(card: CardId, stats: &mut Stats) {
-    match card {
-        CardId::Power => stats.damage *= 1.2,
-        CardId::Speed => stats.move_speed *= 1.2,
-        CardId::Jump => stats.jump_force *= 1.2,
-        CardId::Poison => stats.poison_damage += 5.0,
-        CardId::Slow => stats.slow_amount = 0.5,
+/// Removes and returns a card whose rarity passes `allowed`, from the first pool that has one.
+fn take_weighted(
+    pools: &mut [Vec<(Handle<Card>, Rarity)>],
+    draw: &CardDrawBalance,
+    rng: &mut impl Rng,
+    allowed: impl Fn(Rarity) -> bool,
+) -> Option<Handle<Card>> {
+    pools.iter_mut().find_map(|pool| {
+        let index = weighted_pick(pool, draw, rng, &allowed)?;
+        Some(pool.swap_remove(index).0)
+    })
+}
+
+/// Index of a card in `pool` whose rarity passes `allowed`, picked by rarity weight. Falls back
+/// to an even pick when every allowed card has a zero weight.
+fn weighted_pick(
+    pool: &[(Handle<Card>, Rarity)],
+    draw: &CardDrawBalance,
+    rng: &mut impl Rng,
+    allowed: impl Fn(Rarity) -> bool,
+) -> Option<usize> {
+    let candidates: Vec<usize> = (0..pool.len()).filter(|&i| allowed(pool[i].1)).collect();
+    candidates
+        .choose_weighted(rng, |&i| draw.weights.get(pool[i].1))
+        .or_else(|_| candidates.choose(rng).ok_or(()))
+        .ok()
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::RarityWeights;
+
+    fn card(name: &str, rarity: &str, rules: &str) -> Card {
+        ron::from_str(&format!(
+            "(name: {name:?}, description: \"\", rarity: {rarity}, modifiers: [], {rules})"
+        ))
+        .unwrap()
+    }
+
+    fn draw(guaranteed: Vec<Rarity>) -> CardDrawBalance {
+        CardDrawBalance {
+            weights: RarityWeights {
+                common: 60.0,
+                uncommon: 25.0,
+                rare: 12.0,
+                legendary: 3.0,
+            },
+            guaranteed,
+        }
+    }
+
+    /// An app whose card assets hold `library`, and a library resource listing them all.
+    fn load(library: Vec<Card>) -> (App, CardLibrary) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<Card>();
+        let mut cards = app.world.resource_mut::<Assets<Card>>();
+        let handles = library
+            .into_iter()
+            .map(|card| cards.add(card).clone_untyped())
+            .collect();
+        (app, CardLibrary { handles })
+    }
+
+    fn names(app: &App, offer: &[Handle<Card>]) -> Vec<String> {
+        let cards = app.world.resource::<Assets<Card>>();
+        let mut names: Vec<String> = offer
+            .iter()
+            .map(|handle| cards.get(handle).unwrap().name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn can_join_respects_max_stacks() {
+        let mirror = card("Mirror", "Rare", "max_stacks: Some(1)");
+        assert!(mirror.can_join(&[]));
+        assert!(!mirror.can_join(&[&mirror]));
+        let speed = card("Speed", "Common", "");
+        assert!(speed.can_join(&[&speed, &speed]));
+    }
+
+    #[test]
+    fn can_join_needs_every_required_card() {
+        let volatile = card("Volatile", "Rare", "");
+        let power = card("Power", "Common", "");
+        let requires = r#"requires: ["Volatile", "Power"]"#;
+        let detonate = card("Detonate", "Legendary", requires);
+        assert!(!detonate.can_join(&[]));
+        assert!(!detonate.can_join(&[&volatile]));
+        assert!(detonate.can_join(&[&volatile, &power]));
+    }
+
+    #[test]
+    fn can_join_checks_exclusions_both_ways() {
+        let frost = card("Frost", "Common", "");
+        let scorch = card("Scorch", "Uncommon", "excludes: [\"Frost\"]");
+        assert!(!scorch.can_join(&[&frost]));
+        assert!(!frost.can_join(&[&scorch]));
+    }
+
+    #[test]
+    fn random_choices_keeps_every_guarantee() {
+        let mut library: Vec<Card> = (0..8)
+            .map(|i| card(&format!("Common {i}"), "Common", ""))
+            .collect();
+        library.push(card("Uncommon", "Uncommon", ""));
+        library.push(card("Legendary", "Legendary", ""));
+        let (app, library) = load(library);
+        let cards = app.world.resource::<Assets<Card>>();
+        let draw = draw(vec![Rarity::Uncommon, Rarity::Uncommon]);
+        for _ in 0..100 {
+            let offer = random_choices(3, &library, cards, &draw, &Inventory::default(), false);
+            assert_eq!(offer.len(), 3);
+            let better = offer
+                .iter()
+                .filter(|handle| cards.get(*handle).unwrap().rarity >= Rarity::Uncommon)
+                .count();
+            assert_eq!(better, 2);
+        }
+    }
+
+    #[test]
+    fn random_choices_keeps_leader_cards_for_last() {
+        let (app, library) = load(vec![
+            card("A", "Common", ""),
+            card("B", "Common", ""),
+            card("C", "Common", ""),
+            card("Comeback", "Common", "offer_to_leader: false"),
+        ]);
+        let cards = app.world.resource::<Assets<Card>>();
+        let (draw, held) = (draw(Vec::new()), Inventory::default());
+        for _ in 0..50 {
+            let offer = random_choices(3, &library, cards, &draw, &held, true);
+            assert_eq!(names(&app, &offer), ["A", "B", "C"]);
+        }
+        let offer = random_choices(4, &library, cards, &draw, &held, true);
+        assert_eq!(names(&app, &offer), ["A", "B", "C", "Comeback"]);
+    }
+
+    #[test]
+    fn random_choices_comes_up_short_when_the_pool_runs_dry() {
+        let (app, library) = load(vec![
+            card("Mirror", "Rare", "max_stacks: Some(1)"),
+            card("Speed", "Common", ""),
+        ]);
+        let cards = app.world.resource::<Assets<Card>>();
+        let draw = draw(vec![Rarity::Legendary]);
+        let offer = random_choices(3, &library, cards, &draw, &Inventory::default(), false);
+        assert_eq!(names(&app, &offer), ["Mirror", "Speed"]);
+
+        let inventory = Inventory { cards: offer };
+        let offer = random_choices(3, &library, cards, &draw, &inventory, false);
+        assert_eq!(names(&app, &offer), ["Speed"]);
+    }
+
+    /// The card and field named by the error `validate` gives the card written as `text`.
+    fn rejection(text: &str) -> (String, String) {
+        let card: Card = ron::from_str(text).unwrap();
+        match card.validate(std::path::Path::new("test.card.ron")) {
+            Err(CardLoadError::Invalid { card, field, .. }) => (card, field),
+            other => panic!("expected an invalid card, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_a_negative_mul() {
+        let text = r#"(
+            name: "Glass",
+            description: "",
+            rarity: Common,
+            modifiers: [
+                (stat: MoveSpeed, op: Add, value: 10.0),
+                (stat: Damage, op: Mul, value: -2.0),
+            ],
+        )"#;
+        let (card, field) = rejection(text);
+        assert_eq!(card, "Glass");
+        assert_eq!(field, "modifiers[1].value");
+    }
+
+    #[test]
+    fn validate_rejects_zero_max_stacks() {
+        let text = r#"(
+            name: "Mirror",
+            description: "",
+            rarity: Rare,
+            modifiers: [],
+            max_stacks: Some(0),
+        )"#;
+        let (card, field) = rejection(text);
+        assert_eq!(card, "Mirror");
+        assert_eq!(field, "max_stacks");
+    }
+
+    #[test]
+    fn validate_rejects_a_card_requiring_itself() {
+        let text = r#"(
+            name: "Detonate",
+            description: "",
+            rarity: Legendary,
+            modifiers: [],
+            requires: ["Volatile", "Detonate"],
+        )"#;
+        let (card, field) = rejection(text);
+        assert_eq!(card, "Detonate");
+        assert_eq!(field, "requires[1]");
+    }
+
+    #[test]
+    fn validate_rejects_a_slow_above_one() {
+        let text = r#"(
+            name: "Frost",
+            description: "",
+            rarity: Common,
+            modifiers: [],
+            on_hit: [(kind: Slow, magnitude: 1.5, duration: 2.0)],
+        )"#;
+        let (card, field) = rejection(text);
+        assert_eq!(card, "Frost");
+        assert_eq!(field, "on_hit[0].magnitude");
     }
 }
@@ -1,17 +1,84 @@
 use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::hazard::HazardSpec;
+use crate::status::StatusSpec;
 
 #[derive(Component)]
 pub struct Player {
     pub id: usize,
 }
 
+/// What a player wants to do.
+///
+/// Filled in by a controller (keyboard, AI, ...) and consumed by `player_input`, so the
+/// gameplay code does not care where the input came from. `move_x` and `aim` are axes in
+/// `-1.0..=1.0`; `jump` stays set until a tick consumes it. `aim_direction` points the aim
+/// straight at a direction instead, e.g. from an analog stick, and wins over `aim` while set.
+/// `dash` and `block` are latched like `jump`.
+#[derive(Component, Default)]
+pub struct PlayerActions {
+    pub move_x: f32,
+    pub aim: f32,
+    pub aim_direction: Option<Vec2>,
+    pub jump: bool,
+    pub cast: bool,
+    pub dash: bool,
+    pub block: bool,
+}
+
+/// Present while the player stands on something solid.
+#[derive(Component)]
+pub struct Grounded;
+
+/// Timers that make jumping forgiving: a jump still works shortly after walking off a ledge
+/// (`coyote_timer`) and a press shortly before landing is kept until the player lands
+/// (`buffer_timer`).
+#[derive(Component, Default)]
+pub struct JumpState {
+    pub coyote_timer: f32,
+    pub buffer_timer: f32,
+    pub air_jumps_used: u32,
+}
+
 #[derive(Component)]
 pub struct Health {
     pub current: f32,
     pub max: f32,
+    /// Seconds left during which hits pass through the player, e.g. right after a dash.
+    pub invulnerable_timer: f32,
 }
 
+impl Health {
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_timer > 0.0
+    }
+}
+
+/// A raised shield: projectiles touching the player are stopped until `time_left` runs out.
+/// `shield` is the child entity drawing it.
 #[derive(Component)]
+pub struct Blocking {
+    pub time_left: f32,
+    pub shield: Entity,
+}
+
+/// Volleys still to come from a cast, fired every `interval` seconds along the current aim.
+#[derive(Component)]
+pub struct Burst {
+    pub volleys_left: u32,
+    pub interval: f32,
+    pub timer: f32,
+}
+
+/// A dash in progress: the player moves at `velocity` until `time_left` runs out.
+#[derive(Component)]
+pub struct Dashing {
+    pub velocity: Vec2,
+    pub time_left: f32,
+}
+
+#[derive(Component, Clone)]
 pub struct Stats {
     pub move_speed: f32,
     pub jump_force: f32,
@@ -19,47 +86,177 @@ pub struct Stats {
     pub projectile_speed: f32,
     pub shot_cooldown: f32,
     pub cooldown_timer: f32,
-    pub poison_damage: f32,
-    pub slow_amount: f32,
+    /// Extra jumps allowed before touching the ground again.
+    pub air_jumps: f32,
+    pub dash_speed: f32,
+    pub dash_cooldown: f32,
+    pub dash_timer: f32,
+    /// When above zero, dashing teleports the player up to this far instead.
+    pub blink_distance: f32,
+    pub block_duration: f32,
+    pub block_cooldown: f32,
+    pub block_timer: f32,
+    /// When above zero, blocked projectiles are sent back at their owner instead of destroyed.
+    pub reflect: f32,
+    /// Terrain hits each projectile survives.
+    pub bounces: f32,
+    /// Projectiles per volley, fanned out evenly across `spread` degrees.
+    pub projectile_count: f32,
+    pub spread: f32,
+    /// Volleys per cast, `burst_interval` seconds apart.
+    pub burst_count: f32,
+    pub burst_interval: f32,
+    /// Random deviation of each projectile, up to this many degrees either way.
+    pub jitter: f32,
+    /// Degrees per second projectiles turn toward enemies within `homing_radius`.
+    pub homing_turn_rate: f32,
+    pub homing_radius: f32,
+    /// Extra players or shields each projectile passes through before it breaks.
+    pub pierce: f32,
+    /// When above zero, projectiles explode with this radius where they break.
+    pub explosion_radius: f32,
+    /// Speed a hit knocks the victim back at, along the projectile's path.
+    pub knockback: f32,
+    /// Share of the damage dealt by projectiles that heals the shooter.
+    pub lifesteal: f32,
+    /// Status effects every projectile puts on the players it hits, gathered from cards.
+    pub on_hit: Vec<StatusSpec>,
+    /// Hazards every projectile leaves where it breaks, gathered from cards.
+    pub on_impact: Vec<HazardSpec>,
     pub aim_angle: f32,
 }
 
+/// The tunable fields of [`Stats`] that cards are allowed to modify.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Stat {
+    MoveSpeed,
+    JumpForce,
+    Damage,
+    ProjectileSpeed,
+    ShotCooldown,
+    AirJumps,
+    DashSpeed,
+    DashCooldown,
+    BlinkDistance,
+    BlockDuration,
+    BlockCooldown,
+    Reflect,
+    Bounces,
+    ProjectileCount,
+    Spread,
+    BurstCount,
+    BurstInterval,
+    Jitter,
+    HomingTurnRate,
+    HomingRadius,
+    Pierce,
+    ExplosionRadius,
+    Knockback,
+    Lifesteal,
+}
+
+impl Stats {
+    pub fn get_mut(&mut self, stat: Stat) -> &mut f32 {
+        match stat {
+            Stat::MoveSpeed => &mut self.move_speed,
+            Stat::JumpForce => &mut self.jump_force,
+            Stat::Damage => &mut self.damage,
+            Stat::ProjectileSpeed => &mut self.projectile_speed,
+            Stat::ShotCooldown => &mut self.shot_cooldown,
+            Stat::AirJumps => &mut self.air_jumps,
+            Stat::DashSpeed => &mut self.dash_speed,
+            Stat::DashCooldown => &mut self.dash_cooldown,
+            Stat::BlinkDistance => &mut self.blink_distance,
+            Stat::BlockDuration => &mut self.block_duration,
+            Stat::BlockCooldown => &mut self.block_cooldown,
+            Stat::Reflect => &mut self.reflect,
+            Stat::Bounces => &mut self.bounces,
+            Stat::ProjectileCount => &mut self.projectile_count,
+            Stat::Spread => &mut self.spread,
+            Stat::BurstCount => &mut self.burst_count,
+            Stat::BurstInterval => &mut self.burst_interval,
+            Stat::Jitter => &mut self.jitter,
+            Stat::HomingTurnRate => &mut self.homing_turn_rate,
+            Stat::HomingRadius => &mut self.homing_radius,
+            Stat::Pierce => &mut self.pierce,
+            Stat::ExplosionRadius => &mut self.explosion_radius,
+            Stat::Knockback => &mut self.knockback,
+            Stat::Lifesteal => &mut self.lifesteal,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Projectile {
     pub owner: usize,
     pub damage: f32,
+    pub knockback: f32,
+    pub lifesteal: f32,
+}
+
+/// Selects the physics tuning a projectile is spawned with, see `Balance::projectile_physics`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectileKind {
+    Bolt,
+    Bouncer,
 }
 
+/// Terrain hits this projectile survives before the next one destroys it.
 #[derive(Component)]
-pub struct Lifetime {
-    pub time_left: f32,
+pub struct Bounces {
+    pub remaining: u32,
 }
 
-#[derive(Component, Default)]
-pub struct Inventory {
-    pub cards: Vec<crate::cards::CardId>,
+/// Lets a projectile carry on after hitting a player or shield, `remaining` more times. The
+/// entities it went through are remembered so each one is hit at most once.
+#[derive(Component)]
+pub struct Pierce {
+    pub remaining: u32,
+    pub already_hit: Vec<Entity>,
 }
 
+/// Makes the projectile explode where it breaks, whether on a player, the terrain or when its
+/// lifetime runs out. Damage drops linearly with distance to `1.0 - falloff` of the full
+/// amount at the edge of `radius`.
 #[derive(Component)]
-pub struct PoisonEffect {
-    pub damage_per_second: f32,
-    pub duration: f32,
+pub struct Explosive {
+    pub radius: f32,
+    pub falloff: f32,
 }
 
+/// Steers the projectile toward the nearest enemy within `acquire_radius`, turning at most
+/// `turn_rate` radians per second.
 #[derive(Component)]
-pub struct Poisoned {
-    pub damage_per_second: f32,
-    pub timer: Timer,
+pub struct Homing {
+    pub turn_rate: f32,
+    pub acquire_radius: f32,
 }
 
+/// Level geometry that stops projectiles.
+#[derive(Component)]
+pub struct Terrain;
+
 #[derive(Component)]
-pub struct SlowEffect {
-    pub amount: f32,
-    pub duration: f32,
+pub struct Lifetime {
+    pub time_left: f32,
 }
 
+#[derive(Component, Default)]
+pub struct Inventory {
+    pub cards: Vec<Handle<crate::cards::Card>>,
+}
+
+/// Status effects a projectile applies to the player it hits.
+#[derive(Component)]
+pub struct OnHitEffects(pub Vec<StatusSpec>);
+
+/// Hazards a projectile leaves behind where it breaks.
+#[derive(Component)]
+pub struct ImpactHazards(pub Vec<HazardSpec>);
+
+/// A player who was just knocked back and cannot steer until the timer runs out, so the push
+/// is not cancelled by their own movement.
 #[derive(Component)]
-pub struct Slowed {
-    pub amount: f32,
+pub struct HitStun {
     pub timer: Timer,
 }
@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Something a player can do, independent of the key that triggers it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    AimUp,
+    AimDown,
+    Jump,
+    Cast,
+    Dash,
+    Block,
+}
+
+/// Keys bound to each action for one player. Any of the listed keys triggers the action.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PlayerBindings(pub BTreeMap<Action, Vec<KeyCode>>);
+
+impl PlayerBindings {
+    pub fn new(bindings: impl IntoIterator<Item = (Action, Vec<KeyCode>)>) -> Self {
+        Self(bindings.into_iter().collect())
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn pressed(&self, keyboard: &Input<KeyCode>, action: Action) -> bool {
+        keyboard.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, keyboard: &Input<KeyCode>, action: Action) -> bool {
+        keyboard.any_just_pressed(self.keys(action).iter().copied())
+    }
+}
+
+/// Key bindings for every keyboard player, keyed by `Player::id`. Players without an entry
+/// are not driven from the keyboard.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Controls {
+    pub players: BTreeMap<usize, PlayerBindings>,
+    /// Analog stick deflection, from `0.0` up to but not including `1.0`, below which gamepad
+    /// sticks are ignored.
+    #[serde(default = "default_stick_deadzone")]
+    pub stick_deadzone: f32,
+    /// Player whose aim follows the mouse cursor, casts with the left button and dashes with the
+    /// right one, if any.
+    #[serde(default)]
+    pub mouse_player: Option<usize>,
+}
+
+fn default_stick_deadzone() -> f32 {
+    0.2
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        use Action::*;
+        Self {
+            players: BTreeMap::from([
+                (
+                    1,
+                    PlayerBindings::new([
+                        (MoveLeft, vec![KeyCode::A]),
+                        (MoveRight, vec![KeyCode::D]),
+                        (AimUp, vec![KeyCode::Q, KeyCode::W]),
+                        (AimDown, vec![KeyCode::E, KeyCode::S]),
+                        (Jump, vec![KeyCode::Space]),
+                        (Cast, vec![KeyCode::ControlLeft]),
+                        (Dash, vec![KeyCode::ShiftLeft]),
+                        (Block, vec![KeyCode::F]),
+                    ]),
+                ),
+                (
+                    2,
+                    PlayerBindings::new([
+                        (MoveLeft, vec![KeyCode::Left]),
+                        (MoveRight, vec![KeyCode::Right]),
+                        (AimUp, vec![KeyCode::Comma, KeyCode::I]),
+                        (AimDown, vec![KeyCode::Period, KeyCode::K]),
+                        (Jump, vec![KeyCode::Up]),
+                        (Cast, vec![KeyCode::Return]),
+                        (Dash, vec![KeyCode::ShiftRight]),
+                        (Block, vec![KeyCode::Down]),
+                    ]),
+                ),
+            ]),
+            stick_deadzone: default_stick_deadzone(),
+            mouse_player: None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ControlsError {
+    #[error("could not access controls file {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not parse controls file {path:?}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: ron::error::SpannedError,
+    },
+    #[error("could not write controls file {path:?}: {source}")]
+    Serialize { path: PathBuf, source: ron::Error },
+    #[error("controls file {path:?}: `stick_deadzone` must be in 0..1, got {value}")]
+    Deadzone { path: PathBuf, value: f32 },
+}
+
+impl Controls {
+    pub fn load(path: &Path) -> Result<Self, ControlsError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ControlsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let controls: Self = ron::from_str(&text).map_err(|source| ControlsError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        // At 1 or above the stick rescaling divides by zero or flips direction.
+        if !(0.0..1.0).contains(&controls.stick_deadzone) {
+            return Err(ControlsError::Deadzone {
+                path: path.to_path_buf(),
+                value: controls.stick_deadzone,
+            });
+        }
+        Ok(controls)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ControlsError> {
+        let text = ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default().compact_arrays(true),
+        )
+        .map_err(|source| ControlsError::Serialize {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        std::fs::write(path, text).map_err(|source| ControlsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Reads the bindings from `path`, falling back to the defaults when the file does not
+    /// exist or cannot be read. Nothing is written; run with `--write-controls` to get a file
+    /// to edit.
+    pub fn load_or_default(path: &Path) -> Self {
+        if !path.exists() {
+            info!("No controls file at {path:?}, using default controls");
+            return Self::default();
+        }
+        Self::load(path).unwrap_or_else(|err| {
+            warn!("{err}, using default controls");
+            Self::default()
+        })
+    }
+}
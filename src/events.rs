@@ -5,3 +5,43 @@ pub struct PlayerKilled {
     pub winner: usize,
     pub loser: usize,
 }
+
+/// A projectile hit the terrain and was destroyed.
+#[derive(Event)]
+pub struct ProjectileImpact {
+    pub owner: usize,
+    pub position: Vec2,
+}
+
+/// An explosive projectile went off. Damage and knockback are applied from this event, and
+/// visuals or audio can react to it as well.
+#[derive(Event)]
+pub struct Explosion {
+    pub owner: usize,
+    pub position: Vec2,
+    pub radius: f32,
+    pub falloff: f32,
+    /// Damage at the centre of the blast.
+    pub damage: f32,
+    /// Share of the damage dealt to other players that heals the owner.
+    pub lifesteal: f32,
+    /// Player the projectile broke on, who already took its damage and is left out of the blast.
+    pub direct_hit: Option<usize>,
+}
+
+/// A player regained health, from lifesteal or a card effect. `amount` is what was actually
+/// restored after capping at the maximum.
+#[derive(Event)]
+pub struct Healed {
+    pub player: usize,
+    pub amount: f32,
+}
+
+/// A player's shield stopped a projectile. Card block effects react to this.
+#[derive(Event)]
+pub struct Blocked {
+    pub player: usize,
+    pub attacker: usize,
+    pub position: Vec2,
+    pub reflected: bool,
+}
@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// The kinds of lingering area a projectile can leave behind where it lands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum HazardKind {
+    /// Burns the opponents standing in it for `magnitude` damage per second.
+    Fire,
+    /// Poisons the opponents standing in it for `magnitude` damage per second.
+    Poison,
+    /// Takes `magnitude` (0 to 1) off the friction of every player standing on it.
+    Ice,
+}
+
+impl HazardKind {
+    /// Tint of the area drawn under the hazard.
+    pub fn color(self) -> Color {
+        match self {
+            Self::Fire => Color::rgba(1.0, 0.4, 0.1, 0.35),
+            Self::Poison => Color::rgba(0.4, 0.9, 0.2, 0.35),
+            Self::Ice => Color::rgba(0.6, 0.9, 1.0, 0.35),
+        }
+    }
+}
+
+/// A hazard left by a projectile when it breaks, as written in card files.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HazardSpec {
+    pub kind: HazardKind,
+    pub radius: f32,
+    pub duration: f32,
+    pub magnitude: f32,
+}
+
+/// A lingering area in the arena. Its sensor collider finds the players inside, and a
+/// [`Lifetime`](crate::components::Lifetime) removes it. Projectiles that land inside a hazard
+/// of the same kind from the same player renew it rather than piling up more.
+#[derive(Component, Clone, Debug)]
+pub struct Hazard {
+    pub owner: usize,
+    pub kind: HazardKind,
+    pub radius: f32,
+    pub magnitude: f32,
+}
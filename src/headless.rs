@@ -0,0 +1,229 @@
+use bevy::app::{AppExit, ScheduleRunnerPlugin};
+use bevy::core::TaskPoolOptions;
+use bevy::ecs::schedule::{ExecutorKind, ScheduleLabel};
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+use crate::cards::Card;
+use crate::components::{Health, Inventory, Player, Projectile};
+use crate::events::PlayerKilled;
+use crate::resources::{AiRng, CardSelection, RoundManager, ShotRng};
+use crate::states::GameState;
+use crate::systems;
+use crate::{MagicDuelConfig, MagicDuelPlugin, MagicDuelSet};
+
+/// Simulation step used for both `Time` and the physics world.
+const TIMESTEP: f32 = 1.0 / 60.0;
+
+/// Rounds where neither bot lands the final hit within this many simulated seconds are
+/// awarded to the player with more health, so a stuck bot cannot stall the run.
+const ROUND_TIME_LIMIT: f32 = 15.0;
+
+/// Options for running matches without a window, parsed from the command line:
+/// `--headless [--rounds N] [--seed S]`.
+pub struct HeadlessConfig {
+    pub rounds: u32,
+    pub seed: u64,
+}
+
+impl HeadlessConfig {
+    /// Returns `None` unless `--headless` was passed.
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut headless = false;
+        let mut config = Self {
+            rounds: 1000,
+            seed: 0,
+        };
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => headless = true,
+                "--rounds" => config.rounds = parse_value(&arg, args.next())?,
+                "--seed" => config.seed = parse_value(&arg, args.next())?,
+                other => return Err(format!("unknown argument `{other}`")),
+            }
+        }
+        Ok(headless.then_some(config))
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    value
+        .as_deref()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("`{flag}` expects a number"))
+}
+
+#[derive(Resource)]
+struct Simulation {
+    rounds_left: u32,
+    rounds: u32,
+    matches: u32,
+    round_wins: [u32; 2],
+    match_wins: [u32; 2],
+    timeouts: u32,
+    round_time: f32,
+    started: Instant,
+}
+
+/// Adds the plugins and bot systems for a windowless, fixed-timestep run.
+pub fn configure(app: &mut App, config: HeadlessConfig) {
+    let step = Duration::from_secs_f32(TIMESTEP);
+    app.add_plugins((
+        MinimalPlugins
+            .set(TaskPoolPlugin {
+                task_pool_options: TaskPoolOptions::with_num_threads(1),
+            })
+            .set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)),
+        LogPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+    ))
+    // Every update advances time by exactly one fixed tick, as fast as the CPU allows.
+    .insert_resource(TimeUpdateStrategy::ManualDuration(step))
+    .insert_resource(FixedTime::new(step))
+    .add_plugins(MagicDuelPlugin {
+        config: MagicDuelConfig {
+            keyboard_controls: false,
+            presentation: false,
+            ..default()
+        },
+    });
+    // The world is tiny, so spreading systems across threads costs more than it saves.
+    single_threaded(app, First);
+    single_threaded(app, PreUpdate);
+    single_threaded(app, StateTransition);
+    single_threaded(app, FixedUpdate);
+    single_threaded(app, Update);
+    single_threaded(app, PostUpdate);
+    single_threaded(app, Last);
+    app.insert_resource(AiRng(StdRng::seed_from_u64(config.seed)))
+        .insert_resource(ShotRng(StdRng::seed_from_u64(config.seed)))
+        .insert_resource(Simulation {
+            rounds_left: config.rounds,
+            rounds: 0,
+            matches: 0,
+            round_wins: [0; 2],
+            match_wins: [0; 2],
+            timeouts: 0,
+            round_time: 0.0,
+            started: Instant::now(),
+        })
+        .add_systems(
+            PreUpdate,
+            systems::ai_input
+                .in_set(MagicDuelSet::Input)
+                .run_if(in_state(GameState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                auto_pick_card.run_if(in_state(GameState::CardSelection)),
+                auto_restart.run_if(in_state(GameState::GameOver)),
+                round_timeout.run_if(in_state(GameState::InGame)),
+                track_rounds,
+            ),
+        );
+}
+
+fn single_threaded(app: &mut App, label: impl ScheduleLabel) {
+    app.edit_schedule(label, |schedule| {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    });
+}
+
+fn auto_pick_card(
+    mut rng: ResMut<AiRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut selection: ResMut<CardSelection>,
+    mut players: Query<(&Player, &mut Inventory)>,
+    cards: Res<Assets<Card>>,
+) {
+    let index = rng.0.gen_range(0..selection.choices.len().max(1));
+    systems::pick_card(index, &mut selection, &mut players, &cards, &mut next_state);
+}
+
+fn auto_restart(
+    mut sim: ResMut<Simulation>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut manager: ResMut<RoundManager>,
+    mut players: Query<(&Player, &mut Transform, &mut Health)>,
+    projectiles: Query<Entity, With<Projectile>>,
+    mut commands: Commands,
+) {
+    sim.matches += 1;
+    let winner = usize::from(manager.p2_score > manager.p1_score);
+    sim.match_wins[winner] += 1;
+    systems::restart_match(
+        &mut next_state,
+        &mut manager,
+        &mut players,
+        &projectiles,
+        &mut commands,
+    );
+}
+
+fn round_timeout(
+    time: Res<Time>,
+    mut sim: ResMut<Simulation>,
+    players: Query<(&Player, &Health)>,
+    mut kill_writer: EventWriter<PlayerKilled>,
+) {
+    sim.round_time += time.delta_seconds();
+    if sim.round_time < ROUND_TIME_LIMIT {
+        return;
+    }
+    let mut by_health: Vec<_> = players.iter().collect();
+    by_health.sort_by(|(_, a), (_, b)| a.current.total_cmp(&b.current));
+    if let [(loser, _), .., (winner, _)] = by_health[..] {
+        warn!(
+            "Round timed out after {ROUND_TIME_LIMIT}s, awarding it to player {}",
+            winner.id
+        );
+        sim.timeouts += 1;
+        kill_writer.send(PlayerKilled {
+            winner: winner.id,
+            loser: loser.id,
+        });
+    }
+    sim.round_time = 0.0;
+}
+
+fn track_rounds(
+    time: Res<Time>,
+    mut sim: ResMut<Simulation>,
+    mut reader: EventReader<PlayerKilled>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in reader.iter() {
+        sim.rounds += 1;
+        sim.round_time = 0.0;
+        if let Some(wins) = sim.round_wins.get_mut(event.winner.wrapping_sub(1)) {
+            *wins += 1;
+        }
+        sim.rounds_left = sim.rounds_left.saturating_sub(1);
+    }
+    if sim.rounds_left == 0 {
+        let elapsed = sim.started.elapsed().as_secs_f32();
+        info!(
+            "Simulated {} rounds ({} matches, {:.0} min of play) in {:.1}s, {:.0} rounds/min. \
+             Round wins P1 {} - P2 {}, match wins P1 {} - P2 {}, {} rounds timed out",
+            sim.rounds,
+            sim.matches,
+            time.elapsed_seconds() / 60.0,
+            elapsed,
+            sim.rounds as f32 / elapsed * 60.0,
+            sim.round_wins[0],
+            sim.round_wins[1],
+            sim.match_wins[0],
+            sim.match_wins[1],
+            sim.timeouts,
+        );
+        exit.send(AppExit);
+    }
+}
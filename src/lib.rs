@@ -0,0 +1,308 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::path::PathBuf;
+
+pub mod balance;
+pub mod cards;
+pub mod components;
+pub mod controls;
+pub mod events;
+pub mod hazard;
+pub mod headless;
+pub mod modifiers;
+pub mod physics;
+pub mod resources;
+pub mod states;
+pub mod status;
+pub mod systems;
+
+use balance::{Balance, BalanceLoader};
+use cards::{Card, CardLoader};
+use controls::Controls;
+use events::{Blocked, Explosion, Healed, PlayerKilled, ProjectileImpact};
+use resources::{CardLibrary, CardSelection, GamepadAssignments, RoundManager, ShotRng};
+use states::GameState;
+
+/// Settings for [`MagicDuelPlugin`], also available to systems as a resource.
+#[derive(Resource, Clone, Debug)]
+pub struct MagicDuelConfig {
+    /// Rounds a player has to win to take the match.
+    pub rounds_to_win: u32,
+    /// Drive players, card picks and the restart prompt from the keyboard, mouse and gamepads.
+    pub keyboard_controls: bool,
+    /// RON file holding the per-player key bindings. The defaults are used if it is missing.
+    pub controls_path: PathBuf,
+    /// Spawn the camera, HUD and menus. Turn off when the host renders the game itself.
+    pub presentation: bool,
+}
+
+impl Default for MagicDuelConfig {
+    fn default() -> Self {
+        Self {
+            rounds_to_win: RoundManager::default().rounds_to_win,
+            keyboard_controls: true,
+            controls_path: PathBuf::from("controls.ron"),
+            presentation: true,
+        }
+    }
+}
+
+/// Labels for the gameplay systems, so host apps can order their own systems around them.
+///
+/// `Input` runs in `PreUpdate` once Bevy has read the input devices. The simulation sets run
+/// in `FixedUpdate`, chained in declaration order right after the physics step, so a tick
+/// always sees movement, then hits, then damage over time, then the round result.
+/// `Presentation` runs in `Update`.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MagicDuelSet {
+    /// Fills in `PlayerActions` from a controller.
+    Input,
+    /// Recomputing stats, then walking, jumping, aiming and casting.
+    Movement,
+    /// Cooldowns, projectile lifetimes and projectile hits.
+    Combat,
+    /// Damage over time and other status effects.
+    Effects,
+    /// Scoring and state changes once a player is killed.
+    RoundResolution,
+    /// HUD and other visual feedback.
+    Presentation,
+}
+
+/// The whole game: add it next to a set of Bevy plugins that provide assets and time.
+///
+/// Rapier is added with the game's scale and stepped in `FixedUpdate` unless the host already
+/// registered it.
+#[derive(Default)]
+pub struct MagicDuelPlugin {
+    pub config: MagicDuelConfig,
+}
+
+impl Plugin for MagicDuelPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<RapierPhysicsPlugin<NoUserData>>() {
+            add_fixed_physics(app);
+        }
+        app.insert_resource(self.config.clone())
+            .configure_set(PreUpdate, MagicDuelSet::Input.after(InputSystem))
+            .configure_sets(
+                FixedUpdate,
+                (
+                    MagicDuelSet::Movement,
+                    MagicDuelSet::Combat,
+                    MagicDuelSet::Effects,
+                    MagicDuelSet::RoundResolution,
+                )
+                    .chain(),
+            )
+            .add_plugins((CombatPlugin, CardsPlugin, RoundFlowPlugin));
+        if self.config.presentation {
+            app.add_plugins(HudPlugin);
+        }
+    }
+}
+
+/// Arena, players, projectiles, hazards and status effects.
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Balance>()
+            .init_asset_loader::<BalanceLoader>()
+            .init_resource::<Balance>()
+            .init_resource::<ShotRng>()
+            .add_event::<Blocked>()
+            .add_event::<ProjectileImpact>()
+            .add_event::<Explosion>()
+            .add_event::<Healed>()
+            .add_systems(Startup, systems::setup)
+            .add_systems(
+                FixedUpdate,
+                (
+                    (
+                        systems::stat_recompute_system,
+                        systems::ground_detection,
+                        apply_deferred,
+                        systems::player_input,
+                        systems::dash_system,
+                        systems::block_system,
+                    )
+                        .chain()
+                        .in_set(MagicDuelSet::Movement),
+                    (
+                        systems::update_cooldowns,
+                        systems::burst_fire_system,
+                        systems::projectile_cleanup,
+                        systems::homing_system,
+                        // A projectile can hit a player and the terrain, and run out of time,
+                        // in the same tick. Each of these despawns it, so applying the commands
+                        // in between leaves the later systems nothing to break a second time.
+                        (
+                            systems::projectile_player_collision,
+                            apply_deferred,
+                            systems::projectile_terrain_collision,
+                            apply_deferred,
+                            systems::lifetime_system,
+                        )
+                            .chain(),
+                        systems::fall_out_system,
+                        systems::explosion_system
+                            .after(systems::lifetime_system)
+                            .after(systems::projectile_terrain_collision)
+                            .after(systems::projectile_player_collision),
+                    )
+                        .in_set(MagicDuelSet::Combat),
+                    (
+                        systems::hazard_system,
+                        systems::status_effect_system,
+                        systems::hit_stun_system,
+                        systems::block_effects_system,
+                    )
+                        .in_set(MagicDuelSet::Effects),
+                )
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (balance::hot_reload_system, cards::check_card_names),
+            );
+        if keyboard_controls(app) {
+            let controls = app
+                .world
+                .get_resource::<MagicDuelConfig>()
+                .map_or_else(Controls::default, |config| {
+                    Controls::load_or_default(&config.controls_path)
+                });
+            app.insert_resource(controls)
+                .init_resource::<GamepadAssignments>()
+                .add_systems(
+                    PreUpdate,
+                    (
+                        systems::assign_gamepads,
+                        systems::local_input.run_if(in_state(GameState::InGame)),
+                    )
+                        .chain()
+                        .in_set(MagicDuelSet::Input),
+                );
+        }
+    }
+}
+
+/// Card definitions and the pick between rounds.
+pub struct CardsPlugin;
+
+impl Plugin for CardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Card>()
+            .init_asset_loader::<CardLoader>()
+            .init_resource::<CardSelection>()
+            .init_resource::<CardLibrary>();
+        if keyboard_controls(app) {
+            app.add_systems(
+                Update,
+                (systems::card_input_system, systems::card_click_system)
+                    .run_if(in_state(GameState::CardSelection)),
+            );
+        }
+    }
+}
+
+/// Scoring, the round/card/game over state machine and match restarts.
+pub struct RoundFlowPlugin;
+
+impl Plugin for RoundFlowPlugin {
+    fn build(&self, app: &mut App) {
+        let rounds_to_win = app
+            .world
+            .get_resource::<MagicDuelConfig>()
+            .map_or(RoundManager::default().rounds_to_win, |c| c.rounds_to_win);
+        app.insert_resource(RoundManager {
+            rounds_to_win,
+            ..default()
+        })
+        .add_state::<GameState>()
+        .add_event::<PlayerKilled>()
+        .add_systems(
+            FixedUpdate,
+            systems::round_manager
+                .in_set(MagicDuelSet::RoundResolution)
+                .run_if(in_state(GameState::InGame)),
+        );
+        if keyboard_controls(app) {
+            app.add_systems(
+                Update,
+                systems::game_over_input.run_if(in_state(GameState::GameOver)),
+            );
+        }
+    }
+}
+
+/// Camera, HUD, card picker and game over screens.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, (systems::setup_camera, systems::setup_hud))
+            .add_systems(OnEnter(GameState::CardSelection), systems::setup_card_ui)
+            .add_systems(OnExit(GameState::CardSelection), systems::cleanup_card_ui)
+            .add_systems(OnEnter(GameState::GameOver), systems::setup_game_over)
+            .add_systems(OnExit(GameState::GameOver), systems::cleanup_game_over)
+            .add_systems(
+                Update,
+                (systems::update_hud, systems::heal_flash)
+                    .in_set(MagicDuelSet::Presentation)
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+/// Steps Rapier once per fixed tick, ahead of the gameplay sets, so physics and gameplay
+/// advance together regardless of frame rate.
+fn add_fixed_physics(app: &mut App) {
+    let dt = app
+        .world
+        .get_resource::<FixedTime>()
+        .map_or(FixedTime::default().period, |time| time.period);
+    app.add_plugins(
+        RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0).with_default_system_setup(false),
+    )
+    .configure_sets(
+        FixedUpdate,
+        (
+            PhysicsSet::SyncBackend,
+            PhysicsSet::SyncBackendFlush,
+            PhysicsSet::StepSimulation,
+            PhysicsSet::Writeback,
+        )
+            .chain()
+            .before(MagicDuelSet::Movement),
+    )
+    .add_systems(
+        FixedUpdate,
+        (
+            RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackend)
+                .in_set(PhysicsSet::SyncBackend),
+            RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackendFlush)
+                .in_set(PhysicsSet::SyncBackendFlush),
+            RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::StepSimulation)
+                .in_set(PhysicsSet::StepSimulation),
+            RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::Writeback)
+                .in_set(PhysicsSet::Writeback),
+        ),
+    );
+    app.world
+        .resource_mut::<RapierConfiguration>()
+        .timestep_mode = TimestepMode::Fixed {
+        dt: dt.as_secs_f32(),
+        substeps: 1,
+    };
+}
+
+fn keyboard_controls(app: &App) -> bool {
+    app.world
+        .get_resource::<MagicDuelConfig>()
+        .is_none_or(|config| config.keyboard_controls)
+}
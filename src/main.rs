@@ -1,77 +1,72 @@
+use bevy::asset::ChangeWatcher;
 use bevy::prelude::*;
 use bevy::render::{
     settings::{Backends, WgpuSettings},
     RenderPlugin,
 };
-use bevy_rapier2d::prelude::*;
 use bevy_rapier2d::render::RapierDebugRenderPlugin;
+use std::time::Duration;
 
-mod cards;
-mod components;
-mod events;
-mod resources;
-mod states;
-mod systems;
-
-use events::PlayerKilled;
-use resources::{CardSelection, RoundManager};
-use states::GameState;
+use magic_duel::controls::Controls;
+use magic_duel::headless;
+use magic_duel::{MagicDuelConfig, MagicDuelPlugin};
 
 fn main() {
-    App::new()
-        .add_plugins(
-            DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: "Magic Duel".into(),
-                        resolution: (800., 600.).into(),
-                        ..default()
-                    }),
+    if std::env::args().nth(1).as_deref() == Some("--write-controls") {
+        write_default_controls();
+        return;
+    }
+    let mut app = App::new();
+    match headless::HeadlessConfig::from_args(std::env::args().skip(1)) {
+        Ok(Some(config)) => headless::configure(&mut app, config),
+        Ok(None) => configure_windowed(&mut app),
+        Err(err) => {
+            eprintln!(
+                "{err}\nusage: magic_duel [--headless [--rounds N] [--seed S] | --write-controls]"
+            );
+            std::process::exit(2);
+        }
+    }
+    app.run();
+}
+
+/// Saves the default key bindings where the game reads them from, for players to edit.
+fn write_default_controls() {
+    let path = MagicDuelConfig::default().controls_path;
+    if let Err(err) = Controls::default().save(&path) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+    println!("Wrote default controls to {path:?}");
+}
+
+fn configure_windowed(app: &mut App) {
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Magic Duel".into(),
+                    resolution: (800., 600.).into(),
                     ..default()
-                })
-                .set(RenderPlugin {
-                    wgpu_settings: WgpuSettings {
-                        backends: Some(Backends::VULKAN),
-                        ..default()
-                    },
                 }),
-        )
-        .add_plugins((
-            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0),
-            RapierDebugRenderPlugin::default(),
-        ))
-        .init_resource::<RoundManager>()
-        .init_resource::<CardSelection>()
-        .add_state::<GameState>()
-        .add_event::<PlayerKilled>()
-        .add_systems(Startup, (systems::setup, systems::setup_hud))
-        .add_systems(OnEnter(GameState::CardSelection), systems::setup_card_ui)
-        .add_systems(OnExit(GameState::CardSelection), systems::cleanup_card_ui)
-        .add_systems(OnEnter(GameState::GameOver), systems::setup_game_over)
-        .add_systems(OnExit(GameState::GameOver), systems::cleanup_game_over)
-        .add_systems(
-            Update,
-            (
-                systems::player_input,
-                systems::update_cooldowns,
-                systems::projectile_cleanup,
-                systems::lifetime_system,
-                systems::poison_damage_system,
-                systems::slow_system,
-                systems::projectile_player_collision,
-                systems::round_manager,
-                systems::update_hud,
-            )
-                .run_if(in_state(GameState::InGame)),
-        )
-        .add_systems(
-            Update,
-            (systems::card_input_system, systems::card_click_system)
-                .run_if(in_state(GameState::CardSelection)),
-        )
-        .add_systems(
-            Update,
-            (systems::game_over_input).run_if(in_state(GameState::GameOver)),
-        )
-        .run();
+                ..default()
+            })
+            .set(AssetPlugin {
+                watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+                ..default()
+            })
+            .set(RenderPlugin {
+                wgpu_settings: WgpuSettings {
+                    backends: Some(Backends::VULKAN),
+                    ..default()
+                },
+            }),
+    )
+    .add_plugins((
+        MagicDuelPlugin::default(),
+        RapierDebugRenderPlugin::default(),
+    ));
+    if !cfg!(feature = "gamepad") {
+        warn!("Built without the `gamepad` feature, so controllers are ignored");
+    }
 }
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::cards::{Card, ModifierOp};
+use crate::components::{Inventory, Stat, Stats};
+use crate::status::{StatusEffects, StatusKind};
+
+/// A player's stats before any card or status effect, taken from the balance file.
+#[derive(Component, Clone)]
+pub struct BaseStats(pub Stats);
+
+/// What put a [`StatModifier`] on a player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModifierSource {
+    /// The card at this position in the player's inventory.
+    Card(usize),
+    Status(StatusKind),
+}
+
+/// One change to a stat, applied on top of [`BaseStats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatModifier {
+    pub stat: Stat,
+    pub op: ModifierOp,
+    pub value: f32,
+    pub source: ModifierSource,
+}
+
+/// Every modifier on a player, gathered from their cards and status effects whenever either
+/// changes.
+#[derive(Component, Clone, Debug, Default)]
+pub struct StatModifiers(pub Vec<StatModifier>);
+
+/// The order ops are applied in, whatever order the modifiers were gathered in. Each `Set`
+/// overrides the ones before it, and `Max` and `Min` clamp the final value from below and above.
+const OP_ORDER: [ModifierOp; 5] = [
+    ModifierOp::Add,
+    ModifierOp::Mul,
+    ModifierOp::Set,
+    ModifierOp::Max,
+    ModifierOp::Min,
+];
+
+/// Applies `modifiers` to `stats`, one op at a time in [`OP_ORDER`].
+pub fn apply_modifiers(stats: &mut Stats, modifiers: &[StatModifier]) {
+    for op in OP_ORDER {
+        for modifier in modifiers.iter().filter(|m| m.op == op) {
+            let value = stats.get_mut(modifier.stat);
+            match op {
+                ModifierOp::Add => *value += modifier.value,
+                ModifierOp::Mul => *value *= modifier.value,
+                ModifierOp::Set => *value = modifier.value,
+                ModifierOp::Max => *value = value.max(modifier.value),
+                ModifierOp::Min => *value = value.min(modifier.value),
+            }
+        }
+    }
+}
+
+/// Rebuilds a player's effective stats from their base stats, the cards they hold and the
+/// status effects on them. Cooldown timers and aim belong to the live player and are kept.
+pub fn recompute_stats(
+    base: &BaseStats,
+    inventory: &Inventory,
+    effects: &StatusEffects,
+    cards: &Assets<Card>,
+    modifiers: &mut StatModifiers,
+    stats: &mut Stats,
+) {
+    modifiers.0.clear();
+    let mut rebuilt = base.0.clone();
+    for (slot, card) in inventory
+        .cards
+        .iter()
+        .enumerate()
+        .filter_map(|(slot, handle)| Some((slot, cards.get(handle)?)))
+    {
+        modifiers
+            .0
+            .extend(card.modifiers.iter().map(|m| StatModifier {
+                stat: m.stat,
+                op: m.op,
+                value: m.value,
+                source: ModifierSource::Card(slot),
+            }));
+        rebuilt.on_hit.extend(card.on_hit.iter().cloned());
+        rebuilt.on_impact.extend(card.on_impact.iter().cloned());
+    }
+    modifiers.0.extend(effects.stat_modifiers());
+    apply_modifiers(&mut rebuilt, &modifiers.0);
+    rebuilt.cooldown_timer = stats.cooldown_timer;
+    rebuilt.dash_timer = stats.dash_timer;
+    rebuilt.block_timer = stats.block_timer;
+    rebuilt.aim_angle = stats.aim_angle;
+    *stats = rebuilt;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::Balance;
+
+    fn modifier(stat: Stat, op: ModifierOp, value: f32) -> StatModifier {
+        StatModifier {
+            stat,
+            op,
+            value,
+            source: ModifierSource::Card(0),
+        }
+    }
+
+    fn damage_after(modifiers: &[StatModifier]) -> f32 {
+        let mut stats = Balance::default().base_stats();
+        stats.damage = 10.0;
+        apply_modifiers(&mut stats, modifiers);
+        stats.damage
+    }
+
+    #[test]
+    fn adds_apply_before_muls_whatever_the_order() {
+        let add = modifier(Stat::Damage, ModifierOp::Add, 5.0);
+        let mul = modifier(Stat::Damage, ModifierOp::Mul, 2.0);
+        assert_eq!(damage_after(&[mul.clone(), add.clone()]), 30.0);
+        assert_eq!(damage_after(&[add, mul]), 30.0);
+    }
+
+    #[test]
+    fn the_last_set_wins_over_adds_and_muls() {
+        assert_eq!(
+            damage_after(&[
+                modifier(Stat::Damage, ModifierOp::Set, 4.0),
+                modifier(Stat::Damage, ModifierOp::Mul, 3.0),
+                modifier(Stat::Damage, ModifierOp::Set, 8.0),
+                modifier(Stat::Damage, ModifierOp::Add, 1.0),
+            ]),
+            8.0
+        );
+    }
+
+    #[test]
+    fn max_and_min_clamp_the_final_value() {
+        let floor = modifier(Stat::Damage, ModifierOp::Max, 2.0);
+        let cap = modifier(Stat::Damage, ModifierOp::Min, 6.0);
+        assert_eq!(
+            damage_after(&[floor.clone(), modifier(Stat::Damage, ModifierOp::Mul, 0.1)]),
+            2.0
+        );
+        assert_eq!(
+            damage_after(&[cap.clone(), modifier(Stat::Damage, ModifierOp::Set, 50.0)]),
+            6.0
+        );
+        // The cap is applied last, so it wins over a floor above it.
+        let high_floor = modifier(Stat::Damage, ModifierOp::Max, 9.0);
+        assert_eq!(damage_after(&[cap, high_floor, floor]), 6.0);
+    }
+
+    #[test]
+    fn only_the_named_stat_changes() {
+        let mut stats = Balance::default().base_stats();
+        let speed = stats.move_speed;
+        apply_modifiers(&mut stats, &[modifier(Stat::Damage, ModifierOp::Set, 1.0)]);
+        assert_eq!(stats.damage, 1.0);
+        assert_eq!(stats.move_speed, speed);
+    }
+}
@@ -0,0 +1,57 @@
+use bevy_rapier2d::prelude::*;
+
+/// Level geometry.
+pub const TERRAIN_GROUP: Group = Group::GROUP_1;
+/// Every projectile, whoever fired it.
+pub const PROJECTILE_GROUP: Group = Group::GROUP_2;
+/// Lingering areas left by projectiles.
+pub const HAZARD_GROUP: Group = Group::GROUP_3;
+/// The bits handed out to players by [`player_group`].
+const FIRST_PLAYER_BIT: u32 = 8;
+
+/// Friction of a player's collider on solid ground. Ice hazards lower it.
+pub const PLAYER_FRICTION: f32 = 0.5;
+
+/// Each player gets a group of their own so projectiles can skip the player who fired them.
+/// Ids beyond the available bits wrap around and share a group.
+pub fn player_group(id: usize) -> Group {
+    let bits = 32 - FIRST_PLAYER_BIT;
+    Group::from_bits_truncate(1 << (FIRST_PLAYER_BIT + id as u32 % bits))
+}
+
+/// All player groups together.
+pub fn all_players() -> Group {
+    Group::from_bits_truncate(u32::MAX << FIRST_PLAYER_BIT)
+}
+
+pub fn terrain_groups() -> CollisionGroups {
+    CollisionGroups::new(TERRAIN_GROUP, Group::ALL)
+}
+
+pub fn player_groups(id: usize) -> CollisionGroups {
+    CollisionGroups::new(player_group(id), Group::ALL)
+}
+
+/// Projectiles touch the terrain and every player but their owner, and never each other.
+pub fn projectile_groups(owner: usize) -> CollisionGroups {
+    CollisionGroups::new(
+        PROJECTILE_GROUP,
+        TERRAIN_GROUP | (all_players() - player_group(owner)),
+    )
+}
+
+/// Projectiles only push against the terrain. Players get collision events but no contact
+/// forces, so a hit does not shove them around.
+pub fn projectile_solver_groups() -> SolverGroups {
+    SolverGroups::new(PROJECTILE_GROUP, TERRAIN_GROUP)
+}
+
+/// Hazard sensors only look for players.
+pub fn hazard_groups() -> CollisionGroups {
+    CollisionGroups::new(HAZARD_GROUP, all_players())
+}
+
+/// What a player stands on or blinks into: the terrain and other players, never projectiles.
+pub fn solid_filter() -> CollisionGroups {
+    CollisionGroups::new(Group::ALL, TERRAIN_GROUP | all_players())
+}
@@ -1,4 +1,7 @@
 use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
 
 #[derive(Resource)]
 pub struct RoundManager {
@@ -10,7 +13,17 @@ pub struct RoundManager {
 #[derive(Resource, Default)]
 pub struct CardSelection {
     pub loser: Option<usize>,
-    pub choices: Vec<crate::cards::Card>,
+    pub choices: Vec<Handle<crate::cards::Card>>,
+}
+
+/// Keeps the balance file loaded so edits on disk are picked up while playing.
+#[derive(Resource)]
+pub struct BalanceHandle(pub Handle<crate::balance::Balance>);
+
+/// Keeps every card loaded from `assets/cards` alive for the whole session.
+#[derive(Resource, Default)]
+pub struct CardLibrary {
+    pub handles: Vec<HandleUntyped>,
 }
 
 #[derive(Resource, Clone)]
@@ -29,3 +42,23 @@ impl Default for RoundManager {
         }
     }
 }
+
+/// Seeded random source for bot decisions, so simulations can be replayed.
+#[derive(Resource)]
+pub struct AiRng(pub StdRng);
+
+/// Random source for shot jitter. Seeded from entropy unless the host inserts its own.
+#[derive(Resource)]
+pub struct ShotRng(pub StdRng);
+
+impl Default for ShotRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+/// Which connected gamepad drives which player, keyed by `Player::id`.
+#[derive(Resource, Default)]
+pub struct GamepadAssignments {
+    pub players: BTreeMap<usize, Gamepad>,
+}
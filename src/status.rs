@@ -0,0 +1,381 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::balance::CrowdControlBalance;
+use crate::cards::ModifierOp;
+use crate::components::Stat;
+use crate::modifiers::{ModifierSource, StatModifier};
+
+/// The kinds of lingering effect a hit or card can put on a player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum StatusKind {
+    /// Deals `magnitude` damage per second.
+    Poison,
+    /// Deals `magnitude` damage per second, tracked apart from poison so the two stack.
+    Burn,
+    /// Takes `magnitude` (0 to 1) off movement speed.
+    Slow,
+    /// Stops the player from moving, aiming, jumping and casting.
+    Stun,
+    /// Like a stun, then shatters for `magnitude` damage when it wears off. Also built up by
+    /// landing stacking slows on a player whose slow is already at its most stacks.
+    Freeze,
+    /// Stops the player from casting.
+    Silence,
+    /// Takes `magnitude` (0 to 1) off the damage of the player's projectiles.
+    Weaken,
+}
+
+impl StatusKind {
+    /// Effects measured as a share of something, whose magnitude must stay within 0 to 1.
+    pub fn is_fraction(self) -> bool {
+        matches!(self, Self::Slow | Self::Weaken)
+    }
+
+    pub fn deals_damage(self) -> bool {
+        matches!(self, Self::Poison | Self::Burn)
+    }
+
+    /// Effects that lock a player out entirely, subject to diminishing returns.
+    pub fn is_crowd_control(self) -> bool {
+        matches!(self, Self::Stun | Self::Freeze)
+    }
+}
+
+/// How a new application combines with an effect of the same kind that is still running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum Stacking {
+    /// Restarts the timer and keeps the stronger magnitude.
+    #[default]
+    Refresh,
+    /// Adds the magnitude as another stack, up to `max_stacks`, and restarts the timer.
+    Intensity,
+    /// Adds the duration to the time left, up to `max_stacks` durations, and keeps the stronger
+    /// magnitude.
+    Duration,
+}
+
+/// One application of a status effect, as written in card files and carried by projectiles.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatusSpec {
+    pub kind: StatusKind,
+    #[serde(default)]
+    pub magnitude: f32,
+    pub duration: f32,
+    #[serde(default)]
+    pub stacking: Stacking,
+    #[serde(default = "default_max_stacks")]
+    pub max_stacks: u32,
+}
+
+fn default_max_stacks() -> u32 {
+    1
+}
+
+/// A status effect currently running on a player.
+#[derive(Clone, Debug)]
+pub struct ActiveStatus {
+    pub kind: StatusKind,
+    /// The player who applied it most recently, credited with its damage.
+    pub source: usize,
+    pub magnitude: f32,
+    pub stacks: u32,
+    pub time_left: f32,
+    /// Share of the damage dealt that heals `source`.
+    pub lifesteal: f32,
+}
+
+/// Every status effect on a player, at most one entry per kind.
+#[derive(Component, Clone, Debug, Default)]
+pub struct StatusEffects {
+    pub active: Vec<ActiveStatus>,
+    /// Stuns and freezes landed since the diminishing returns last wore off.
+    pub crowd_control_count: u32,
+    /// Seconds until `crowd_control_count` resets.
+    pub diminishing_timer: f32,
+    /// Slows landed on a fully stacked slow, counting towards a freeze.
+    pub freeze_buildup: u32,
+}
+
+impl StatusEffects {
+    /// Adds `spec` from player `source`, combining it with a running effect of the same kind
+    /// according to its stacking rule. Stuns and freezes are shortened by diminishing returns,
+    /// and a stacking slow that is already at its most stacks builds up a freeze instead.
+    pub fn apply(
+        &mut self,
+        spec: &StatusSpec,
+        source: usize,
+        lifesteal: f32,
+        balance: &CrowdControlBalance,
+    ) {
+        if spec.kind.is_crowd_control() {
+            if self.crowd_control_count >= balance.immune_after {
+                return;
+            }
+            let duration =
+                spec.duration * balance.diminishing.powi(self.crowd_control_count as i32);
+            self.crowd_control_count += 1;
+            self.diminishing_timer = balance.diminishing_reset;
+            if spec.kind == StatusKind::Freeze {
+                // A frozen player is no longer slowed, and thaws with a clean slate.
+                self.active.retain(|a| a.kind != StatusKind::Slow);
+                self.freeze_buildup = 0;
+            }
+            self.stack(
+                &StatusSpec {
+                    duration,
+                    ..spec.clone()
+                },
+                source,
+                lifesteal,
+            );
+            return;
+        }
+        if spec.kind == StatusKind::Slow && spec.stacking == Stacking::Intensity {
+            let max_stacks = spec.max_stacks.max(1);
+            let maxed = self
+                .get(StatusKind::Slow)
+                .is_some_and(|slow| slow.stacks >= max_stacks);
+            if maxed && !self.has(StatusKind::Freeze) {
+                self.freeze_buildup += 1;
+                if self.freeze_buildup >= balance.freeze_buildup {
+                    let freeze = StatusSpec {
+                        kind: StatusKind::Freeze,
+                        magnitude: balance.shatter_damage,
+                        duration: balance.freeze_duration,
+                        stacking: Stacking::Refresh,
+                        max_stacks: 1,
+                    };
+                    self.apply(&freeze, source, 0.0, balance);
+                    return;
+                }
+            }
+        }
+        self.stack(spec, source, lifesteal);
+    }
+
+    /// Counts down the diminishing returns, and lets freeze build-up lapse with the slow.
+    pub fn tick(&mut self, dt: f32) {
+        if self.diminishing_timer > 0.0 {
+            self.diminishing_timer -= dt;
+            if self.diminishing_timer <= 0.0 {
+                self.crowd_control_count = 0;
+            }
+        }
+        if !self.has(StatusKind::Slow) {
+            self.freeze_buildup = 0;
+        }
+    }
+
+    fn stack(&mut self, spec: &StatusSpec, source: usize, lifesteal: f32) {
+        let max_stacks = spec.max_stacks.max(1);
+        let Some(active) = self.active.iter_mut().find(|a| a.kind == spec.kind) else {
+            self.active.push(ActiveStatus {
+                kind: spec.kind,
+                source,
+                magnitude: spec.magnitude,
+                stacks: 1,
+                time_left: spec.duration,
+                lifesteal,
+            });
+            return;
+        };
+        active.source = source;
+        active.lifesteal = lifesteal;
+        match spec.stacking {
+            Stacking::Refresh => {
+                active.magnitude = active.magnitude.max(spec.magnitude);
+                active.time_left = active.time_left.max(spec.duration);
+            }
+            Stacking::Intensity => {
+                if active.stacks < max_stacks {
+                    active.stacks += 1;
+                    active.magnitude += spec.magnitude;
+                }
+                active.time_left = active.time_left.max(spec.duration);
+            }
+            Stacking::Duration => {
+                active.magnitude = active.magnitude.max(spec.magnitude);
+                active.time_left =
+                    (active.time_left + spec.duration).min(spec.duration * max_stacks as f32);
+            }
+        }
+    }
+
+    pub fn get(&self, kind: StatusKind) -> Option<&ActiveStatus> {
+        self.active.iter().find(|a| a.kind == kind)
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.get(kind).is_some()
+    }
+
+    /// Total magnitude of `kind`, or zero when the player is free of it.
+    pub fn magnitude(&self, kind: StatusKind) -> f32 {
+        self.get(kind).map_or(0.0, |a| a.magnitude)
+    }
+
+    /// Whether the player is currently locked out of moving, aiming and casting.
+    pub fn is_incapacitated(&self) -> bool {
+        self.has(StatusKind::Stun) || self.has(StatusKind::Freeze)
+    }
+
+    pub fn can_cast(&self) -> bool {
+        !self.is_incapacitated() && !self.has(StatusKind::Silence)
+    }
+
+    /// The stat changes of the running effects: slows cut movement speed and weakens cut
+    /// damage, each by its magnitude.
+    pub fn stat_modifiers(&self) -> impl Iterator<Item = StatModifier> + '_ {
+        self.active.iter().filter_map(|effect| {
+            let stat = match effect.kind {
+                StatusKind::Slow => Stat::MoveSpeed,
+                StatusKind::Weaken => Stat::Damage,
+                _ => return None,
+            };
+            Some(StatModifier {
+                stat,
+                op: ModifierOp::Mul,
+                value: 1.0 - effect.magnitude.min(1.0),
+                source: ModifierSource::Status(effect.kind),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(kind: StatusKind, magnitude: f32, duration: f32, stacking: Stacking) -> StatusSpec {
+        StatusSpec {
+            kind,
+            magnitude,
+            duration,
+            stacking,
+            max_stacks: 3,
+        }
+    }
+
+    fn balance() -> CrowdControlBalance {
+        CrowdControlBalance {
+            diminishing: 0.5,
+            diminishing_reset: 4.0,
+            immune_after: 3,
+            freeze_buildup: 2,
+            freeze_duration: 1.0,
+            shatter_damage: 15.0,
+        }
+    }
+
+    #[test]
+    fn refresh_keeps_the_stronger_magnitude_and_longer_timer() {
+        let mut effects = StatusEffects::default();
+        let strong = spec(StatusKind::Burn, 5.0, 2.0, Stacking::Refresh);
+        let long = spec(StatusKind::Burn, 3.0, 4.0, Stacking::Refresh);
+        effects.apply(&strong, 1, 0.0, &balance());
+        effects.apply(&long, 2, 0.0, &balance());
+        let burn = effects.get(StatusKind::Burn).unwrap();
+        assert_eq!(burn.magnitude, 5.0);
+        assert_eq!(burn.time_left, 4.0);
+        assert_eq!(burn.stacks, 1);
+        assert_eq!(burn.source, 2);
+        assert_eq!(effects.active.len(), 1);
+    }
+
+    #[test]
+    fn intensity_adds_magnitude_up_to_max_stacks() {
+        let mut effects = StatusEffects::default();
+        let poison = spec(StatusKind::Poison, 2.0, 3.0, Stacking::Intensity);
+        for _ in 0..5 {
+            effects.apply(&poison, 1, 0.0, &balance());
+        }
+        let active = effects.get(StatusKind::Poison).unwrap();
+        assert_eq!(active.stacks, 3);
+        assert_eq!(active.magnitude, 6.0);
+        assert_eq!(active.time_left, 3.0);
+    }
+
+    #[test]
+    fn duration_adds_time_up_to_max_stacks_durations() {
+        let mut effects = StatusEffects::default();
+        let silence = spec(StatusKind::Silence, 0.0, 2.0, Stacking::Duration);
+        effects.apply(&silence, 1, 0.0, &balance());
+        effects.apply(&silence, 1, 0.0, &balance());
+        assert_eq!(effects.get(StatusKind::Silence).unwrap().time_left, 4.0);
+        effects.apply(&silence, 1, 0.0, &balance());
+        effects.apply(&silence, 1, 0.0, &balance());
+        assert_eq!(effects.get(StatusKind::Silence).unwrap().time_left, 6.0);
+    }
+
+    #[test]
+    fn different_kinds_run_side_by_side() {
+        let mut effects = StatusEffects::default();
+        let burn = spec(StatusKind::Burn, 5.0, 2.0, Stacking::Refresh);
+        let poison = spec(StatusKind::Poison, 3.0, 2.0, Stacking::Refresh);
+        effects.apply(&burn, 1, 0.0, &balance());
+        effects.apply(&poison, 1, 0.0, &balance());
+        assert_eq!(effects.magnitude(StatusKind::Burn), 5.0);
+        assert_eq!(effects.magnitude(StatusKind::Poison), 3.0);
+    }
+
+    #[test]
+    fn repeated_stuns_diminish_until_immune() {
+        let mut effects = StatusEffects::default();
+        let stun = spec(StatusKind::Stun, 0.0, 2.0, Stacking::Refresh);
+        let mut durations = Vec::new();
+        for _ in 0..4 {
+            effects.active.clear();
+            effects.apply(&stun, 1, 0.0, &balance());
+            durations.push(effects.get(StatusKind::Stun).map(|s| s.time_left));
+        }
+        assert_eq!(durations, [Some(2.0), Some(1.0), Some(0.5), None]);
+    }
+
+    #[test]
+    fn diminishing_returns_wear_off() {
+        let mut effects = StatusEffects::default();
+        let stun = spec(StatusKind::Stun, 0.0, 2.0, Stacking::Refresh);
+        for _ in 0..3 {
+            effects.apply(&stun, 1, 0.0, &balance());
+        }
+        effects.active.clear();
+        effects.tick(3.9);
+        effects.apply(&stun, 1, 0.0, &balance());
+        assert!(!effects.has(StatusKind::Stun));
+        effects.tick(4.1);
+        effects.apply(&stun, 1, 0.0, &balance());
+        assert_eq!(effects.get(StatusKind::Stun).unwrap().time_left, 2.0);
+    }
+
+    #[test]
+    fn slows_on_a_full_slow_build_up_a_freeze() {
+        let mut effects = StatusEffects::default();
+        let slow = spec(StatusKind::Slow, 0.2, 2.0, Stacking::Intensity);
+        for _ in 0..4 {
+            effects.apply(&slow, 1, 0.0, &balance());
+        }
+        assert_eq!(effects.freeze_buildup, 1);
+        assert!(!effects.has(StatusKind::Freeze));
+        effects.apply(&slow, 2, 0.0, &balance());
+        let freeze = effects.get(StatusKind::Freeze).unwrap();
+        assert_eq!(freeze.magnitude, 15.0);
+        assert_eq!(freeze.time_left, 1.0);
+        assert_eq!(freeze.source, 2);
+        assert!(!effects.has(StatusKind::Slow));
+        assert_eq!(effects.freeze_buildup, 0);
+    }
+
+    #[test]
+    fn freeze_buildup_lapses_with_the_slow() {
+        let mut effects = StatusEffects::default();
+        let slow = spec(StatusKind::Slow, 0.2, 2.0, Stacking::Intensity);
+        for _ in 0..4 {
+            effects.apply(&slow, 1, 0.0, &balance());
+        }
+        effects.active.clear();
+        effects.tick(0.1);
+        assert_eq!(effects.freeze_buildup, 0);
+    }
+}
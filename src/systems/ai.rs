@@ -0,0 +1,85 @@
+use bevy::ecs::query::Has;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::balance::Balance;
+use crate::components::{Grounded, Player, PlayerActions, Stats};
+use crate::resources::AiRng;
+use crate::systems::{wrap_angle, AIM_SPEED};
+
+/// Bots stay this far from the centre so they never walk off the ground.
+const ARENA_HALF_WIDTH: f32 = 350.0;
+
+/// Simple bot used by the headless simulation: walks straight at the nearest opponent,
+/// hopping as it goes, and lobs shots along the high ballistic arc so they clear the block
+/// in the middle of the arena, falling back to the flat arc when a lob would expire before
+/// it lands.
+pub fn ai_input(
+    mut rng: ResMut<AiRng>,
+    rapier: Res<RapierConfiguration>,
+    balance: Res<Balance>,
+    players: Query<(Entity, &Transform, &Stats, &Velocity, Has<Grounded>), With<Player>>,
+    mut actions: Query<(Entity, &mut PlayerActions)>,
+    mut letting_go: Local<HashSet<Entity>>,
+) {
+    // Rapier takes gravity in world units and scales it down itself.
+    let gravity = -rapier.gravity.y * balance.bolt.gravity_scale;
+    for (entity, mut action) in actions.iter_mut() {
+        let Ok((_, transform, stats, velocity, grounded)) = players.get(entity) else {
+            continue;
+        };
+        let pos = transform.translation.truncate();
+        let Some(target) = players
+            .iter()
+            .filter(|(other, ..)| *other != entity)
+            .map(|(_, t, ..)| t.translation.truncate())
+            .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)))
+        else {
+            *action = PlayerActions::default();
+            continue;
+        };
+
+        let delta = target - pos;
+        let aim = match lob(delta, stats.projectile_speed, gravity, &balance) {
+            Some(aim) => aim,
+            None if delta.x < 0.0 => std::f32::consts::FRAC_PI_4 * 3.0,
+            None => std::f32::consts::FRAC_PI_4,
+        };
+        action.move_x = if pos.x.abs() > ARENA_HALF_WIDTH {
+            -pos.x.signum()
+        } else {
+            delta.x.signum()
+        };
+        // Walking into the side of a block holds a bot up against it in mid-air, so it lets
+        // go until it has landed.
+        if grounded {
+            letting_go.remove(&entity);
+        } else if action.move_x != 0.0 && velocity.linvel.y.abs() < 1.0 {
+            letting_go.insert(entity);
+        }
+        if letting_go.contains(&entity) {
+            action.move_x = 0.0;
+        }
+        action.aim = (wrap_angle(aim - stats.aim_angle) / AIM_SPEED).clamp(-1.0, 1.0);
+        action.jump = rng.0.gen_bool(0.1);
+        action.cast = true;
+    }
+}
+
+/// Aim angle that lands a shot `delta` away, or `None` when the target is out of range.
+fn lob(delta: Vec2, speed: f32, gravity: f32, balance: &Balance) -> Option<f32> {
+    let discriminant =
+        speed.powi(4) - gravity * (gravity * delta.x * delta.x + 2.0 * delta.y * speed * speed);
+    if discriminant < 0.0 {
+        return None;
+    }
+    let high = (speed * speed + discriminant.sqrt()).atan2(gravity * delta.x);
+    let flight_time = delta.x.abs() / (speed * high.cos().abs()).max(f32::EPSILON);
+    if flight_time < balance.projectile_lifetime {
+        Some(high)
+    } else {
+        Some((speed * speed - discriminant.sqrt()).atan2(gravity * delta.x))
+    }
+}
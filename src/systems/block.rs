@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::balance::Balance;
+use crate::cards::{BlockEffect, Card};
+use crate::components::{Blocking, Health, Inventory, Player, PlayerActions, Stats};
+use crate::events::{Blocked, Healed};
+use crate::status::{Stacking, StatusEffects, StatusKind, StatusSpec};
+
+use super::heal_player;
+
+/// Size of the shield drawn around a blocking player.
+const SHIELD_SIZE: f32 = 44.0;
+
+/// Counts down the block cooldown, raises a shield when a player blocks and the cooldown allows,
+/// and lowers it again once the active window is over.
+pub fn block_system(
+    mut commands: Commands,
+    time: Res<FixedTime>,
+    mut players: Query<(
+        Entity,
+        &mut PlayerActions,
+        &mut Stats,
+        &StatusEffects,
+        Option<&mut Blocking>,
+    )>,
+) {
+    let dt = time.period.as_secs_f32();
+    for (entity, mut actions, mut stats, effects, blocking) in players.iter_mut() {
+        let wants_block = std::mem::take(&mut actions.block);
+        if stats.block_timer > 0.0 && !effects.is_incapacitated() {
+            stats.block_timer -= dt;
+        }
+        if let Some(mut blocking) = blocking {
+            blocking.time_left -= dt;
+            if blocking.time_left <= 0.0 {
+                commands.entity(blocking.shield).despawn_recursive();
+                commands.entity(entity).remove::<Blocking>();
+            }
+            continue;
+        }
+        if !wants_block || stats.block_timer > 0.0 || effects.is_incapacitated() {
+            continue;
+        }
+        let shield = commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0.4, 0.7, 1.0, 0.4),
+                    custom_size: Some(Vec2::splat(SHIELD_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, 0.5),
+                ..default()
+            })
+            .id();
+        commands.entity(entity).add_child(shield).insert(Blocking {
+            time_left: stats.block_duration,
+            shield,
+        });
+        stats.block_timer = stats.block_cooldown;
+    }
+}
+
+/// Runs the block effects of every card the blocking player holds.
+pub fn block_effects_system(
+    mut reader: EventReader<Blocked>,
+    cards: Res<Assets<Card>>,
+    balance: Res<Balance>,
+    mut players: Query<(
+        &Player,
+        &Inventory,
+        &mut Health,
+        &mut StatusEffects,
+        &Transform,
+    )>,
+    mut heal_writer: EventWriter<Healed>,
+) {
+    for event in reader.iter() {
+        let effects: Vec<BlockEffect> = players
+            .iter()
+            .filter(|(player, ..)| player.id == event.player)
+            .flat_map(|(_, inventory, ..)| inventory.cards.iter())
+            .filter_map(|handle| cards.get(handle))
+            .flat_map(|card| card.on_block.iter().cloned())
+            .collect();
+        for effect in effects {
+            match effect {
+                BlockEffect::Heal(amount) => heal_player(
+                    players
+                        .iter_mut()
+                        .map(|(player, _, health, ..)| (player, health)),
+                    event.player,
+                    amount,
+                    &mut heal_writer,
+                ),
+                BlockEffect::SlowNearby {
+                    radius,
+                    amount,
+                    duration,
+                } => {
+                    let slow = StatusSpec {
+                        kind: StatusKind::Slow,
+                        magnitude: amount,
+                        duration,
+                        stacking: Stacking::Refresh,
+                        max_stacks: 1,
+                    };
+                    for (player, _, _, mut effects, transform) in players.iter_mut() {
+                        if player.id != event.player
+                            && transform.translation.truncate().distance(event.position) <= radius
+                        {
+                            effects.apply(&slow, event.player, 0.0, &balance.crowd_control);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
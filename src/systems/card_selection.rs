@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
-use crate::cards;
-use crate::components::{Inventory, Player, Stats};
+use crate::cards::Card;
+use crate::components::{Inventory, Player};
 use crate::resources::CardSelection;
 use crate::states::GameState;
 
@@ -13,7 +13,21 @@ pub struct CardButton {
     pub index: usize,
 }
 
-pub fn setup_card_ui(mut commands: Commands, selection: Res<CardSelection>) {
+pub fn setup_card_ui(
+    mut commands: Commands,
+    mut selection: ResMut<CardSelection>,
+    cards: Res<Assets<Card>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // With nothing to pick there is no way off this screen, so go straight back to the duel.
+    if !selection.choices.iter().any(|handle| cards.contains(handle)) {
+        warn!("No cards to offer, skipping card selection");
+        selection.loser = None;
+        selection.choices.clear();
+        next_state.set(GameState::InGame);
+        return;
+    }
+
     // root full screen node
     let root = commands
         .spawn((
@@ -72,7 +86,10 @@ pub fn setup_card_ui(mut commands: Commands, selection: Res<CardSelection>) {
                 ..default()
             })
             .with_children(|row| {
-                for (i, card) in selection.choices.iter().enumerate() {
+                for (i, handle) in selection.choices.iter().enumerate() {
+                    let Some(card) = cards.get(handle) else {
+                        continue;
+                    };
                     row
                         .spawn((
                             ButtonBundle {
@@ -83,9 +100,11 @@ pub fn setup_card_ui(mut commands: Commands, selection: Res<CardSelection>) {
                                     justify_content: JustifyContent::Center,
                                     align_items: AlignItems::Center,
                                     margin: UiRect::all(Val::Px(5.0)),
+                                    border: UiRect::all(Val::Px(3.0)),
                                     ..default()
                                 },
                                 background_color: Color::DARK_GRAY.into(),
+                                border_color: card.rarity.color().into(),
                                 ..default()
                             },
                             CardButton { index: i },
@@ -93,9 +112,17 @@ pub fn setup_card_ui(mut commands: Commands, selection: Res<CardSelection>) {
                         .with_children(|p| {
                             p.spawn(TextBundle::from_sections([
                                 TextSection::new(
-                                    card.name,
+                                    card.name.clone(),
                                     TextStyle { font_size: 24.0, color: Color::WHITE, ..default() },
                                 ),
+                                TextSection::new(
+                                    format!("\n{:?}", card.rarity),
+                                    TextStyle {
+                                        font_size: 14.0,
+                                        color: card.rarity.color(),
+                                        ..default()
+                                    },
+                                ),
                                 TextSection::new(
                                     format!("\n{}", card.description),
                                     TextStyle { font_size: 16.0, color: Color::WHITE, ..default() },
@@ -117,24 +144,42 @@ pub fn card_click_system(
     mut interactions: Query<(&Interaction, &CardButton), Changed<Interaction>>,
     mut next_state: ResMut<NextState<GameState>>,
     mut selection: ResMut<CardSelection>,
-    mut players: Query<(&Player, &mut Stats, &mut Inventory)>,
+    mut players: Query<(&Player, &mut Inventory)>,
+    cards: Res<Assets<Card>>,
 ) {
     for (interaction, button) in &mut interactions {
         if *interaction == Interaction::Pressed {
-            if let Some(card) = selection.choices.get(button.index) {
-                if let Some(loser) = selection.loser {
-                    for (player, mut stats, mut inv) in players.iter_mut() {
-                        if player.id == loser {
-                            cards::apply(card.id, &mut stats);
-                            inv.cards.push(card.id);
-                        }
-                    }
+            pick_card(
+                button.index,
+                &mut selection,
+                &mut players,
+                &cards,
+                &mut next_state,
+            );
+            break;
+        }
+    }
+}
+
+/// Gives the card at `index` of the current offer to the losing player and resumes play. Their
+/// stats pick up the new card on the next tick.
+pub fn pick_card(
+    index: usize,
+    selection: &mut CardSelection,
+    players: &mut Query<(&Player, &mut Inventory)>,
+    cards: &Assets<Card>,
+    next_state: &mut NextState<GameState>,
+) {
+    if let Some(handle) = selection.choices.get(index) {
+        if let (Some(_), Some(loser)) = (cards.get(handle), selection.loser) {
+            for (player, mut inv) in players.iter_mut() {
+                if player.id == loser {
+                    inv.cards.push(handle.clone());
                 }
             }
-            selection.loser = None;
-            selection.choices.clear();
-            next_state.set(GameState::InGame);
-            break;
         }
     }
+    selection.loser = None;
+    selection.choices.clear();
+    next_state.set(GameState::InGame);
 }
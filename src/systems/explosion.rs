@@ -0,0 +1,138 @@
+use bevy::ecs::query::Has;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::balance::Balance;
+use crate::components::{Blocking, Explosive, Health, Player, Projectile};
+use crate::events::{Explosion, Healed, PlayerKilled};
+use crate::physics;
+
+use super::{heal_player, knock_back, opponent};
+
+/// Announces the blast of an explosive projectile that broke at `position`, on player
+/// `direct_hit` if it hit one.
+pub(super) fn detonate(
+    writer: &mut EventWriter<Explosion>,
+    projectile: &Projectile,
+    explosive: &Explosive,
+    position: Vec2,
+    direct_hit: Option<usize>,
+) {
+    writer.send(Explosion {
+        owner: projectile.owner,
+        position,
+        radius: explosive.radius,
+        falloff: explosive.falloff,
+        damage: projectile.damage,
+        lifesteal: projectile.lifesteal,
+        direct_hit,
+    });
+}
+
+/// Hurts and throws back every player Rapier finds inside each blast, less the further they
+/// are from its centre. Shields and invulnerability protect as they do against direct hits,
+/// the player the projectile broke on is spared a second helping, and the owner only takes
+/// `explosion.owner_damage` of the damage. Lifesteal heals the owner for damage dealt to others.
+pub fn explosion_system(
+    mut commands: Commands,
+    rapier: Res<RapierContext>,
+    balance: Res<Balance>,
+    mut explosions: EventReader<Explosion>,
+    mut players: Query<(
+        Entity,
+        &Player,
+        &Transform,
+        &mut Health,
+        &mut ExternalImpulse,
+        &ReadMassProperties,
+        Has<Blocking>,
+    )>,
+    mut kill_writer: EventWriter<PlayerKilled>,
+    mut heal_writer: EventWriter<Healed>,
+) {
+    let mut heals = Vec::new();
+    let ids: Vec<usize> = players.iter().map(|(_, player, ..)| player.id).collect();
+    let filter = QueryFilter::new()
+        .exclude_sensors()
+        .groups(CollisionGroups::new(
+            physics::PROJECTILE_GROUP,
+            physics::all_players(),
+        ));
+    for explosion in explosions.iter() {
+        let mut caught = Vec::new();
+        rapier.intersections_with_shape(
+            explosion.position,
+            0.0,
+            &Collider::ball(explosion.radius),
+            filter,
+            |entity| {
+                caught.push(entity);
+                true
+            },
+        );
+        for entity in caught {
+            let Ok((entity, player, transform, mut health, mut impulse, mass, blocking)) =
+                players.get_mut(entity)
+            else {
+                continue;
+            };
+            let is_owner = player.id == explosion.owner;
+            if blocking
+                || health.is_invulnerable()
+                || explosion.direct_hit == Some(player.id)
+                || (is_owner && balance.explosion.owner_damage <= 0.0)
+            {
+                continue;
+            }
+            let offset = transform.translation.truncate() - explosion.position;
+            let scale = (1.0 - explosion.falloff * (offset.length() / explosion.radius).min(1.0))
+                .clamp(0.0, 1.0);
+            let direction = offset.try_normalize().unwrap_or(Vec2::Y);
+            knock_back(
+                &mut commands,
+                entity,
+                &mut impulse,
+                mass,
+                direction * balance.explosion.knockback * scale,
+                balance.hit_stun,
+            );
+
+            let was_alive = health.current > 0.0;
+            let owner_share = if is_owner {
+                balance.explosion.owner_damage
+            } else {
+                1.0
+            };
+            let damage = explosion.damage * scale * owner_share;
+            if !is_owner && explosion.lifesteal > 0.0 {
+                heals.push((
+                    explosion.owner,
+                    damage.min(health.current.max(0.0)) * explosion.lifesteal,
+                ));
+            }
+            health.current -= damage;
+            if was_alive && health.current <= 0.0 {
+                // Blowing yourself up hands the round to the opponent.
+                let winner = if is_owner {
+                    opponent(&ids, player.id)
+                } else {
+                    explosion.owner
+                };
+                kill_writer.send(PlayerKilled {
+                    winner,
+                    loser: player.id,
+                });
+            }
+        }
+    }
+    for (id, amount) in heals {
+        heal_player(
+            players
+                .iter_mut()
+                .map(|(_, player, _, health, ..)| (player, health)),
+            id,
+            amount,
+            &mut heal_writer,
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::balance::Balance;
+use crate::components::{Burst, Player, Stats};
+use crate::resources::{GameAssets, ShotRng};
+use crate::status::StatusEffects;
+
+use super::spawn_projectile;
+
+/// Fires the first volley of a cast and queues the rest of the burst, if any.
+pub(super) fn start_firing(
+    commands: &mut Commands,
+    rng: &mut StdRng,
+    caster: Entity,
+    owner: usize,
+    stats: &Stats,
+    transform: &Transform,
+    assets: &GameAssets,
+    balance: &Balance,
+) {
+    fire_volley(commands, rng, owner, stats, transform, assets, balance);
+    let volleys = stats.burst_count.floor().max(1.0) as u32;
+    if volleys > 1 {
+        commands.entity(caster).insert(Burst {
+            volleys_left: volleys - 1,
+            interval: stats.burst_interval,
+            timer: stats.burst_interval,
+        });
+    }
+}
+
+/// Fires the remaining volleys of each burst on schedule, along the caster's current aim. A
+/// caster who can no longer cast loses the rest of the burst.
+pub fn burst_fire_system(
+    mut commands: Commands,
+    time: Res<FixedTime>,
+    mut rng: ResMut<ShotRng>,
+    assets: Res<GameAssets>,
+    balance: Res<Balance>,
+    mut casters: Query<(
+        Entity,
+        &Player,
+        &Stats,
+        &StatusEffects,
+        &Transform,
+        &mut Burst,
+    )>,
+) {
+    let dt = time.period.as_secs_f32();
+    for (entity, player, stats, effects, transform, mut burst) in casters.iter_mut() {
+        if !effects.can_cast() {
+            burst.volleys_left = 0;
+        }
+        burst.timer -= dt;
+        while burst.timer <= 0.0 && burst.volleys_left > 0 {
+            fire_volley(
+                &mut commands,
+                &mut rng.0,
+                player.id,
+                stats,
+                transform,
+                &assets,
+                &balance,
+            );
+            burst.volleys_left -= 1;
+            burst.timer += burst.interval;
+        }
+        if burst.volleys_left == 0 {
+            commands.entity(entity).remove::<Burst>();
+        }
+    }
+}
+
+/// Spawns `projectile_count` projectiles fanned evenly across `spread` degrees around the aim,
+/// each nudged by up to `jitter` degrees.
+fn fire_volley(
+    commands: &mut Commands,
+    rng: &mut StdRng,
+    owner: usize,
+    stats: &Stats,
+    transform: &Transform,
+    assets: &GameAssets,
+    balance: &Balance,
+) {
+    let count = stats.projectile_count.floor().max(1.0) as u32;
+    let spread = stats.spread.to_radians();
+    let jitter = stats.jitter.to_radians();
+    for i in 0..count {
+        let offset = if count > 1 {
+            spread * (i as f32 / (count - 1) as f32 - 0.5)
+        } else {
+            0.0
+        };
+        let deviation = if jitter > 0.0 {
+            rng.gen_range(-jitter..=jitter)
+        } else {
+            0.0
+        };
+        spawn_projectile(
+            commands,
+            owner,
+            stats,
+            stats.aim_angle + offset + deviation,
+            transform,
+            assets,
+            balance,
+        );
+    }
+}
@@ -51,19 +51,36 @@ pub fn game_over_input(
     mut commands: Commands,
 ) {
     if keyboard.just_pressed(KeyCode::R) {
-        manager.p1_score = 0;
-        manager.p2_score = 0;
-        for entity in &projectiles {
-            commands.entity(entity).despawn();
-        }
-        for (player, mut transform, mut health) in players.iter_mut() {
-            health.current = health.max;
-            transform.translation = if player.id == 1 {
-                Vec3::new(-100.0, 0.0, 0.0)
-            } else {
-                Vec3::new(100.0, 0.0, 0.0)
-            };
-        }
-        next_state.set(GameState::InGame);
+        restart_match(
+            &mut next_state,
+            &mut manager,
+            &mut players,
+            &projectiles,
+            &mut commands,
+        );
     }
 }
+
+/// Resets scores, health and positions and starts a fresh match.
+pub fn restart_match(
+    next_state: &mut NextState<GameState>,
+    manager: &mut RoundManager,
+    players: &mut Query<(&Player, &mut Transform, &mut Health)>,
+    projectiles: &Query<Entity, With<Projectile>>,
+    commands: &mut Commands,
+) {
+    manager.p1_score = 0;
+    manager.p2_score = 0;
+    for entity in projectiles {
+        commands.entity(entity).despawn();
+    }
+    for (player, mut transform, mut health) in players.iter_mut() {
+        health.current = health.max;
+        transform.translation = if player.id == 1 {
+            Vec3::new(-100.0, 0.0, 0.0)
+        } else {
+            Vec3::new(100.0, 0.0, 0.0)
+        };
+    }
+    next_state.set(GameState::InGame);
+}
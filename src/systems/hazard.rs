@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_rapier2d::prelude::*;
+
+use crate::balance::Balance;
+use crate::components::{Lifetime, Player};
+use crate::hazard::{Hazard, HazardKind, HazardSpec};
+use crate::physics;
+use crate::status::{Stacking, StatusEffects, StatusKind, StatusSpec};
+
+/// Leaves the hazards of a projectile from player `owner` that broke at `position`, or renews
+/// the ones it landed in.
+pub(super) fn spawn_hazards(
+    commands: &mut Commands,
+    existing: &mut Query<(&mut Hazard, &mut Lifetime, &Transform)>,
+    owner: usize,
+    specs: &[HazardSpec],
+    position: Vec2,
+) {
+    for spec in specs {
+        let landed_in = existing.iter_mut().find(|(hazard, _, transform)| {
+            hazard.owner == owner
+                && hazard.kind == spec.kind
+                && transform.translation.truncate().distance(position) <= hazard.radius
+        });
+        if let Some((mut hazard, mut lifetime, _)) = landed_in {
+            hazard.magnitude = hazard.magnitude.max(spec.magnitude);
+            lifetime.time_left = lifetime.time_left.max(spec.duration);
+            continue;
+        }
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: spec.kind.color(),
+                    custom_size: Some(Vec2::splat(spec.radius * 2.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(0.2)),
+                ..default()
+            },
+            Hazard {
+                owner,
+                kind: spec.kind,
+                radius: spec.radius,
+                magnitude: spec.magnitude,
+            },
+            Lifetime {
+                time_left: spec.duration,
+            },
+            Collider::ball(spec.radius),
+            Sensor,
+            physics::hazard_groups(),
+        ));
+    }
+}
+
+/// Applies every hazard to the players Rapier finds inside its sensor. Fire and poison keep
+/// a burn or poison running on the owner's opponents for as long as they stand in it, plus
+/// `hazard_linger` seconds. Ice lowers the friction of each player's collider, which
+/// `player_input` reads as lost traction, and gives it back once they step off.
+pub fn hazard_system(
+    rapier: Res<RapierContext>,
+    balance: Res<Balance>,
+    hazards: Query<(Entity, &Hazard)>,
+    mut players: Query<(Entity, &Player, &mut StatusEffects, &mut Friction)>,
+) {
+    let mut slick: HashMap<Entity, f32> = HashMap::default();
+    for (hazard_entity, hazard) in &hazards {
+        for (a, b, intersecting) in rapier.intersections_with(hazard_entity) {
+            let other = if a == hazard_entity { b } else { a };
+            if !intersecting {
+                continue;
+            }
+            let Ok((_, player, mut effects, _)) = players.get_mut(other) else {
+                continue;
+            };
+            let kind = match hazard.kind {
+                HazardKind::Fire => StatusKind::Burn,
+                HazardKind::Poison => StatusKind::Poison,
+                HazardKind::Ice => {
+                    let share = slick.entry(other).or_default();
+                    *share = share.max(hazard.magnitude.min(1.0));
+                    continue;
+                }
+            };
+            if player.id == hazard.owner {
+                continue;
+            }
+            let spec = StatusSpec {
+                kind,
+                magnitude: hazard.magnitude,
+                duration: balance.hazard_linger,
+                stacking: Stacking::Refresh,
+                max_stacks: 1,
+            };
+            effects.apply(&spec, hazard.owner, 0.0, &balance.crowd_control);
+        }
+    }
+    for (entity, _, _, mut friction) in players.iter_mut() {
+        let coefficient =
+            physics::PLAYER_FRICTION * (1.0 - slick.get(&entity).copied().unwrap_or(0.0));
+        if friction.coefficient != coefficient {
+            friction.coefficient = coefficient;
+        }
+    }
+}
@@ -1,10 +1,16 @@
 use bevy::prelude::*;
 use crate::components::{Health, Player};
+use crate::events::Healed;
 use crate::resources::RoundManager;
 
+/// Seconds a health readout stays tinted after its player heals.
+const HEAL_FLASH: f32 = 0.4;
+
 #[derive(Component)]
 pub struct HealthText {
     pub player_id: usize,
+    /// Time left on the heal tint.
+    pub flash: f32,
 }
 
 #[derive(Component)]
@@ -28,7 +34,7 @@ pub fn setup_hud(mut commands: Commands) {
             top: Val::Px(10.0),
             ..default()
         }),
-        HealthText { player_id: 1 },
+        HealthText { player_id: 1, flash: 0.0 },
     ));
     commands.spawn((
         TextBundle::from_sections([
@@ -47,7 +53,7 @@ pub fn setup_hud(mut commands: Commands) {
             top: Val::Px(10.0),
             ..default()
         }),
-        HealthText { player_id: 2 },
+        HealthText { player_id: 2, flash: 0.0 },
     ));
     commands.spawn((
         TextBundle::from_sections([
@@ -90,3 +96,23 @@ pub fn update_hud(
         text.sections[0].value = format!("Score {} - {}", manager.p1_score, manager.p2_score);
     }
 }
+
+/// Tints a player's health readout green for a moment whenever they heal.
+pub fn heal_flash(
+    time: Res<Time>,
+    mut reader: EventReader<Healed>,
+    mut texts: Query<(&mut HealthText, &mut Text)>,
+) {
+    for event in reader.iter() {
+        for (mut marker, _) in texts.iter_mut() {
+            if marker.player_id == event.player {
+                marker.flash = HEAL_FLASH;
+            }
+        }
+    }
+    for (mut marker, mut text) in texts.iter_mut() {
+        marker.flash = (marker.flash - time.delta_seconds()).max(0.0);
+        let t = marker.flash / HEAL_FLASH;
+        text.sections[0].style.color = Color::rgb(1.0 - t * 0.6, 1.0, 1.0 - t * 0.6);
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::input::gamepad::GamepadConnectionEvent;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::components::{Player, PlayerActions};
+use crate::controls::{Action, Controls, PlayerBindings};
+use crate::resources::GamepadAssignments;
+
+/// Fills in `PlayerActions` from the keyboard bindings, assigned gamepad and, for the mouse
+/// player, the cursor. All of them can be used at the same time; players with none are left
+/// to other controllers.
+pub fn local_input(
+    keyboard: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    controls: Res<Controls>,
+    assignments: Res<GamepadAssignments>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut query: Query<(&Player, &Transform, &mut PlayerActions)>,
+) {
+    let cursor = cursor_world_position(&windows, &cameras);
+    for (player, transform, mut actions) in query.iter_mut() {
+        let bindings = controls.players.get(&player.id);
+        let gamepad = assignments.players.get(&player.id).copied();
+        let uses_mouse = controls.mouse_player == Some(player.id);
+        if bindings.is_none() && gamepad.is_none() && !uses_mouse {
+            continue;
+        }
+        let mut next = PlayerActions {
+            // Jumps, dashes and blocks are latched until a tick consumes them.
+            jump: actions.jump,
+            dash: actions.dash,
+            block: actions.block,
+            ..default()
+        };
+        if let Some(bindings) = bindings {
+            read_keyboard(&keyboard, bindings, &mut next);
+        }
+        if let Some(gamepad) = gamepad {
+            read_gamepad(gamepad, &buttons, &axes, controls.stick_deadzone, &mut next);
+        }
+        if uses_mouse {
+            if let Some(direction) = cursor
+                .map(|cursor| cursor - transform.translation.truncate())
+                .filter(|direction| *direction != Vec2::ZERO)
+            {
+                next.aim_direction = Some(direction);
+            }
+            next.cast |= mouse.pressed(MouseButton::Left);
+            next.dash |= mouse.just_pressed(MouseButton::Right);
+        }
+        *actions = next;
+    }
+}
+
+/// Where the cursor points in the game world, if it is over the window and a camera exists.
+fn cursor_world_position(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cameras: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let cursor = windows.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = cameras.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor)
+}
+
+fn read_keyboard(
+    keyboard: &Input<KeyCode>,
+    bindings: &PlayerBindings,
+    actions: &mut PlayerActions,
+) {
+    let axis = |positive, negative| {
+        f32::from(bindings.pressed(keyboard, positive) as u8)
+            - f32::from(bindings.pressed(keyboard, negative) as u8)
+    };
+    actions.move_x = axis(Action::MoveRight, Action::MoveLeft);
+    actions.aim = axis(Action::AimUp, Action::AimDown);
+    actions.jump |= bindings.just_pressed(keyboard, Action::Jump);
+    actions.cast |= bindings.pressed(keyboard, Action::Cast);
+    actions.dash |= bindings.just_pressed(keyboard, Action::Dash);
+    actions.block |= bindings.just_pressed(keyboard, Action::Block);
+}
+
+/// Left stick moves, the right stick points the aim, South jumps, East dashes, West blocks
+/// and either trigger casts.
+fn read_gamepad(
+    gamepad: Gamepad,
+    buttons: &Input<GamepadButton>,
+    axes: &Axis<GamepadAxis>,
+    deadzone: f32,
+    actions: &mut PlayerActions,
+) {
+    let stick = |x, y| {
+        let axis = |kind| axes.get(GamepadAxis::new(gamepad, kind)).unwrap_or(0.0);
+        apply_deadzone(Vec2::new(axis(x), axis(y)), deadzone)
+    };
+    let movement = stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY);
+    if movement.x != 0.0 {
+        actions.move_x = movement.x;
+    }
+    let aim = stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY);
+    if aim != Vec2::ZERO {
+        actions.aim_direction = Some(aim);
+    }
+    let button = |kind| GamepadButton::new(gamepad, kind);
+    actions.jump |= buttons.just_pressed(button(GamepadButtonType::South));
+    actions.dash |= buttons.just_pressed(button(GamepadButtonType::East));
+    actions.block |= buttons.just_pressed(button(GamepadButtonType::West));
+    actions.cast |= buttons.any_pressed([
+        button(GamepadButtonType::RightTrigger2),
+        button(GamepadButtonType::LeftTrigger2),
+    ]);
+}
+
+/// Zeroes stick input inside the deadzone and rescales the rest back to `0.0..=1.0`, so
+/// there is no jump in speed right at the edge of the deadzone.
+fn apply_deadzone(value: Vec2, deadzone: f32) -> Vec2 {
+    let length = value.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+    value / length * ((length - deadzone) / (1.0 - deadzone)).min(1.0)
+}
+
+/// Hands connected gamepads to players without one, lowest player id first, and frees the
+/// player again when its gamepad is unplugged. Spare gamepads wait until a player is free.
+pub fn assign_gamepads(
+    mut events: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    mut assignments: ResMut<GamepadAssignments>,
+    mut players: Query<(&Player, &mut PlayerActions)>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+
+    let mut disconnected = Vec::new();
+    assignments.players.retain(|&id, gamepad| {
+        let connected = gamepads.contains(*gamepad);
+        if !connected {
+            info!("Gamepad {} unplugged from player {id}", gamepad.id);
+            disconnected.push(id);
+        }
+        connected
+    });
+    for (player, mut actions) in players.iter_mut() {
+        if disconnected.contains(&player.id) {
+            *actions = PlayerActions::default();
+        }
+    }
+
+    let mut free: Vec<usize> = players
+        .iter()
+        .map(|(player, _)| player.id)
+        .filter(|id| !assignments.players.contains_key(id))
+        .collect();
+    free.sort_unstable();
+    let mut spare: Vec<Gamepad> = gamepads
+        .iter()
+        .filter(|gamepad| !assignments.players.values().any(|g| g == gamepad))
+        .collect();
+    spare.sort_by_key(|gamepad| gamepad.id);
+    for (id, gamepad) in free.into_iter().zip(spare) {
+        info!(
+            "Gamepad {} ({}) controls player {id}",
+            gamepad.id,
+            gamepads.name(gamepad).unwrap_or("unknown")
+        );
+        assignments.players.insert(id, gamepad);
+    }
+}
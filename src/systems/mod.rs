@@ -1,22 +1,47 @@
+use crate::balance::Balance;
+use crate::cards::{self, Card};
 use crate::components::Lifetime;
 use crate::components::{
-    Health, Inventory, Player, PoisonEffect, Poisoned, Projectile, SlowEffect, Slowed, Stats,
+    Blocking, Bounces, Burst, Dashing, Explosive, Grounded, Health, HitStun, Homing, ImpactHazards,
+    Inventory, JumpState, OnHitEffects, Pierce, Player, PlayerActions, Projectile, ProjectileKind,
+    Stats, Terrain,
+};
+use crate::events::{Blocked, Explosion, Healed, PlayerKilled, ProjectileImpact};
+use crate::hazard::Hazard;
+use crate::modifiers::{recompute_stats, BaseStats, StatModifiers};
+use crate::physics;
+use crate::resources::{
+    BalanceHandle, CardLibrary, CardSelection, GameAssets, RoundManager, ShotRng,
 };
-use crate::events::PlayerKilled;
-use crate::resources::{CardSelection, GameAssets, RoundManager};
 use crate::states::GameState;
+use crate::status::StatusEffects;
+use bevy::ecs::query::Has;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+mod ai;
+mod block;
 mod card_selection;
+mod explosion;
+mod firing;
 mod game_over;
+mod hazard;
 mod hud;
+mod input;
+mod status;
 
-pub use card_selection::{card_click_system, cleanup_card_ui, setup_card_ui};
-pub use game_over::{cleanup_game_over, game_over_input, setup_game_over};
-pub use hud::{setup_hud, update_hud};
+pub use ai::ai_input;
+pub use block::{block_effects_system, block_system};
+pub use card_selection::{card_click_system, cleanup_card_ui, pick_card, setup_card_ui};
+pub use explosion::explosion_system;
+pub use firing::burst_fire_system;
+pub use game_over::{cleanup_game_over, game_over_input, restart_match, setup_game_over};
+pub use hazard::hazard_system;
+pub use hud::{heal_flash, setup_hud, update_hud};
+pub use input::{assign_gamepads, local_input};
+pub use status::status_effect_system;
 
-pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>, balance: Res<Balance>) {
     let assets = GameAssets {
         player1: asset_server.load("bevy_bird.png"),
         player2: asset_server.load("bevy_icon.png"),
@@ -24,7 +49,11 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     };
     commands.insert_resource(assets.clone());
 
-    commands.spawn(Camera2dBundle::default());
+    commands.insert_resource(BalanceHandle(asset_server.load("game.balance.ron")));
+    match asset_server.load_folder("cards") {
+        Ok(handles) => commands.insert_resource(CardLibrary { handles }),
+        Err(err) => error!("Failed to load card definitions: {err}"),
+    }
 
     // Level geometry
     spawn_block(
@@ -69,27 +98,33 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         },
         Player { id: 1 },
+        physics::player_groups(1),
         Health {
-            current: 100.0,
-            max: 100.0,
-        },
-        Stats {
-            move_speed: 200.0,
-            // 8 m/s jump velocity so players stay on screen
-            jump_force: 8.0,
-            damage: 10.0,
-            projectile_speed: 300.0,
-            shot_cooldown: 0.5,
-            cooldown_timer: 0.0,
-            poison_damage: 0.0,
-            slow_amount: 0.0,
-            aim_angle: 0.0,
+            current: balance.player.max_health,
+            max: balance.player.max_health,
+            invulnerable_timer: 0.0,
         },
+        (
+            balance.base_stats(),
+            BaseStats(balance.base_stats()),
+            StatModifiers::default(),
+        ),
         RigidBody::Dynamic,
-        Collider::cuboid(15.0, 15.0),
+        (
+            Collider::cuboid(15.0, 15.0),
+            Friction {
+                coefficient: physics::PLAYER_FRICTION,
+                combine_rule: CoefficientCombineRule::Min,
+            },
+        ),
         LockedAxes::ROTATION_LOCKED,
         Velocity::zero(),
+        ExternalImpulse::default(),
+        ReadMassProperties::default(),
         crate::components::Inventory::default(),
+        PlayerActions::default(),
+        JumpState::default(),
+        StatusEffects::default(),
     ));
 
     commands.spawn((
@@ -103,30 +138,40 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         },
         Player { id: 2 },
+        physics::player_groups(2),
         Health {
-            current: 100.0,
-            max: 100.0,
-        },
-        Stats {
-            move_speed: 200.0,
-            // 8 m/s jump velocity so players stay on screen
-            jump_force: 8.0,
-            damage: 10.0,
-            projectile_speed: 300.0,
-            shot_cooldown: 0.5,
-            cooldown_timer: 0.0,
-            poison_damage: 0.0,
-            slow_amount: 0.0,
-            aim_angle: 0.0,
+            current: balance.player.max_health,
+            max: balance.player.max_health,
+            invulnerable_timer: 0.0,
         },
+        (
+            balance.base_stats(),
+            BaseStats(balance.base_stats()),
+            StatModifiers::default(),
+        ),
         RigidBody::Dynamic,
-        Collider::cuboid(15.0, 15.0),
+        (
+            Collider::cuboid(15.0, 15.0),
+            Friction {
+                coefficient: physics::PLAYER_FRICTION,
+                combine_rule: CoefficientCombineRule::Min,
+            },
+        ),
         LockedAxes::ROTATION_LOCKED,
         Velocity::zero(),
+        ExternalImpulse::default(),
+        ReadMassProperties::default(),
         crate::components::Inventory::default(),
+        PlayerActions::default(),
+        JumpState::default(),
+        StatusEffects::default(),
     ));
 }
 
+pub fn setup_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
 fn spawn_block(commands: &mut Commands, size: Vec2, pos: Vec2, color: Color) {
     commands.spawn((
         SpriteBundle {
@@ -140,81 +185,309 @@ fn spawn_block(commands: &mut Commands, size: Vec2, pos: Vec2, color: Color) {
         },
         Collider::cuboid(size.x / 2.0, size.y / 2.0),
         RigidBody::Fixed,
+        physics::terrain_groups(),
+        Terrain,
     ));
 }
 
+/// Radians the aim turns per fixed tick at full aim input.
+pub const AIM_SPEED: f32 = 0.03;
+
+/// Maps an angle onto `-PI..PI`, so aim can turn all the way round without growing.
+pub fn wrap_angle(angle: f32) -> f32 {
+    use std::f32::consts::{PI, TAU};
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+/// Starts dashes and blinks, and keeps dashing players at dash speed until the dash ends.
+/// Runs after `player_input` so a dash overrides the normal walking velocity.
+pub fn dash_system(
+    mut commands: Commands,
+    time: Res<FixedTime>,
+    rapier: Res<RapierContext>,
+    balance: Res<Balance>,
+    mut players: Query<(
+        Entity,
+        &mut PlayerActions,
+        &mut Stats,
+        &mut Health,
+        &mut Transform,
+        &mut Velocity,
+        &Collider,
+        &StatusEffects,
+        Option<&mut Dashing>,
+    )>,
+) {
+    let dt = time.period.as_secs_f32();
+    for (
+        entity,
+        mut actions,
+        mut stats,
+        mut health,
+        mut transform,
+        mut velocity,
+        collider,
+        effects,
+        dashing,
+    ) in players.iter_mut()
+    {
+        let wants_dash = std::mem::take(&mut actions.dash);
+        if let Some(mut dashing) = dashing {
+            dashing.time_left -= dt;
+            if dashing.time_left > 0.0 {
+                velocity.linvel = dashing.velocity;
+            } else {
+                commands.entity(entity).remove::<Dashing>();
+            }
+            continue;
+        }
+        if !wants_dash || stats.dash_timer > 0.0 || effects.is_incapacitated() {
+            continue;
+        }
+
+        // Dash the way the player is walking, or the way they aim when standing still.
+        let direction = if actions.move_x != 0.0 {
+            actions.move_x.signum()
+        } else if stats.aim_angle.cos() < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        if stats.blink_distance > 0.0 {
+            let from = transform.translation.truncate();
+            let Some(to) = blink_destination(
+                &rapier,
+                entity,
+                collider,
+                from,
+                direction,
+                stats.blink_distance,
+            ) else {
+                continue;
+            };
+            transform.translation = to.extend(transform.translation.z);
+        } else {
+            let dash_velocity = Vec2::X * direction * stats.dash_speed;
+            velocity.linvel = dash_velocity;
+            commands.entity(entity).insert(Dashing {
+                velocity: dash_velocity,
+                time_left: balance.dash_duration,
+            });
+        }
+        stats.dash_timer = stats.dash_cooldown;
+        health.invulnerable_timer = health.invulnerable_timer.max(balance.dash_invulnerability);
+    }
+}
+
+/// Furthest free spot up to `distance` away along `direction`. Blocked spots are skipped in
+/// small steps back towards the player, so a wall at the destination shortens the blink
+/// instead of leaving the player stuck inside it.
+fn blink_destination(
+    rapier: &RapierContext,
+    entity: Entity,
+    collider: &Collider,
+    from: Vec2,
+    direction: f32,
+    distance: f32,
+) -> Option<Vec2> {
+    const STEP: f32 = 10.0;
+    // Resting contacts overlap by a hair, so test a slightly smaller copy of the collider.
+    let mut probe = collider.clone();
+    probe.set_scale(Vect::splat(0.9), 10);
+    let filter = QueryFilter::new()
+        .exclude_collider(entity)
+        .exclude_sensors()
+        .groups(physics::solid_filter());
+    let mut reach = distance;
+    while reach > 0.0 {
+        let to = from + Vec2::X * direction * reach;
+        if rapier
+            .intersection_with_shape(to, 0.0, &probe, filter)
+            .is_none()
+        {
+            return Some(to);
+        }
+        reach -= STEP;
+    }
+    None
+}
+
+/// How far below a player's feet the ground check looks.
+const GROUND_PROBE: f32 = 2.0;
+
+/// Marks players whose feet are on a block, a platform or another player, by sweeping a thin
+/// box along the bottom of their collider a short way down.
+pub fn ground_detection(
+    mut commands: Commands,
+    rapier: Res<RapierContext>,
+    players: Query<(Entity, &Transform, &Collider, Has<Grounded>), With<Player>>,
+) {
+    for (entity, transform, collider, was_grounded) in &players {
+        let Some(cuboid) = collider.as_cuboid() else {
+            continue;
+        };
+        let half = cuboid.half_extents();
+        // Slightly narrower than the player, so brushing a wall does not count as standing.
+        let probe = Collider::cuboid(half.x * 0.9, 1.0);
+        let feet = transform.translation.truncate() - Vec2::Y * (half.y - 1.0);
+        let filter = QueryFilter::new()
+            .exclude_collider(entity)
+            .exclude_sensors()
+            .groups(physics::solid_filter());
+        let grounded = rapier
+            .cast_shape(feet, 0.0, Vec2::NEG_Y, &probe, GROUND_PROBE, filter)
+            .is_some();
+        if grounded && !was_grounded {
+            commands.entity(entity).insert(Grounded);
+        } else if !grounded && was_grounded {
+            commands.entity(entity).remove::<Grounded>();
+        }
+    }
+}
+
 pub fn player_input(
-    keyboard: Res<Input<KeyCode>>,
     mut commands: Commands,
+    time: Res<FixedTime>,
+    mut rng: ResMut<ShotRng>,
     mut query: Query<(
+        Entity,
         &Player,
+        &mut PlayerActions,
         &mut Stats,
+        &mut JumpState,
+        Has<Grounded>,
         &Transform,
         &mut Velocity,
-        Option<&Slowed>,
+        &StatusEffects,
+        &Friction,
+        Has<HitStun>,
     )>,
     assets: Res<GameAssets>,
+    balance: Res<Balance>,
 ) {
-    for (player, mut stats, transform, mut velocity, slowed) in query.iter_mut() {
-        let mut direction = 0.0;
-        match player.id {
-            1 => {
-                if keyboard.pressed(KeyCode::A) {
-                    direction -= 1.0;
-                }
-                if keyboard.pressed(KeyCode::D) {
-                    direction += 1.0;
-                }
-                if keyboard.pressed(KeyCode::Q) || keyboard.pressed(KeyCode::W) {
-                    stats.aim_angle += 0.03;
-                }
-                if keyboard.pressed(KeyCode::E) || keyboard.pressed(KeyCode::S) {
-                    stats.aim_angle -= 0.03;
-                }
-                if keyboard.just_pressed(KeyCode::Space) && transform.translation.y <= 16.0 {
-                    velocity.linvel.y = stats.jump_force;
-                }
-                if keyboard.pressed(KeyCode::ControlLeft) && stats.cooldown_timer <= 0.0 {
-                    spawn_projectile(&mut commands, player.id, &*stats, transform, &assets);
-                    stats.cooldown_timer = stats.shot_cooldown;
-                }
+    let dt = time.period.as_secs_f32();
+    for (
+        entity,
+        player,
+        mut actions,
+        mut stats,
+        mut jump,
+        grounded,
+        transform,
+        mut velocity,
+        effects,
+        friction,
+        hit_stunned,
+    ) in query.iter_mut()
+    {
+        if effects.is_incapacitated() {
+            // Inputs made while stunned or frozen are dropped rather than queued.
+            actions.jump = false;
+            jump.buffer_timer = 0.0;
+            if !hit_stunned {
+                velocity.linvel.x = 0.0;
             }
-            2 => {
-                if keyboard.pressed(KeyCode::Left) {
-                    direction -= 1.0;
-                }
-                if keyboard.pressed(KeyCode::Right) {
-                    direction += 1.0;
-                }
-                if keyboard.pressed(KeyCode::Comma) || keyboard.pressed(KeyCode::I) {
-                    stats.aim_angle += 0.03;
-                }
-                if keyboard.pressed(KeyCode::Period) || keyboard.pressed(KeyCode::K) {
-                    stats.aim_angle -= 0.03;
-                }
-                if keyboard.just_pressed(KeyCode::Up) && transform.translation.y <= 16.0 {
-                    velocity.linvel.y = stats.jump_force;
-                }
-                if keyboard.pressed(KeyCode::Return) && stats.cooldown_timer <= 0.0 {
-                    spawn_projectile(&mut commands, player.id, &*stats, transform, &assets);
-                    stats.cooldown_timer = stats.shot_cooldown;
-                }
+            continue;
+        }
+        if let Some(direction) = actions.aim_direction {
+            stats.aim_angle = direction.y.atan2(direction.x);
+        } else if actions.aim != 0.0 {
+            stats.aim_angle =
+                wrap_angle(stats.aim_angle + actions.aim.clamp(-1.0, 1.0) * AIM_SPEED);
+        }
+        if grounded {
+            jump.coyote_timer = balance.coyote_time;
+            jump.air_jumps_used = 0;
+        } else {
+            jump.coyote_timer -= dt;
+        }
+        // Jumps are latched by the controller until a tick consumes them.
+        if actions.jump {
+            jump.buffer_timer = balance.jump_buffer;
+            actions.jump = false;
+        } else {
+            jump.buffer_timer -= dt;
+        }
+        if jump.buffer_timer > 0.0 {
+            let can_jump = if jump.coyote_timer > 0.0 {
+                true
+            } else if (jump.air_jumps_used as f32) < stats.air_jumps.floor() {
+                jump.air_jumps_used += 1;
+                true
+            } else {
+                false
+            };
+            if can_jump {
+                velocity.linvel.y = stats.jump_force;
+                jump.coyote_timer = 0.0;
+                jump.buffer_timer = 0.0;
             }
-            _ => {}
         }
-        stats.aim_angle = stats.aim_angle.clamp(0.0, std::f32::consts::PI);
-        let mut speed = stats.move_speed;
-        if let Some(s) = slowed {
-            speed *= 1.0 - s.amount;
+        if actions.cast && stats.cooldown_timer <= 0.0 && effects.can_cast() {
+            firing::start_firing(
+                &mut commands,
+                &mut rng.0,
+                entity,
+                player.id,
+                &stats,
+                transform,
+                &assets,
+                &balance,
+            );
+            stats.cooldown_timer = stats.shot_cooldown;
+        }
+        if hit_stunned {
+            continue;
         }
-        velocity.linvel.x = direction * speed;
+        // On slippery ground the player only gets part of the way to the speed they want.
+        let traction = (friction.coefficient / physics::PLAYER_FRICTION).min(1.0);
+        let target = actions.move_x.clamp(-1.0, 1.0) * stats.move_speed;
+        velocity.linvel.x += (target - velocity.linvel.x) * traction;
+    }
+}
+
+/// Recomputes the stats of players whose base stats, cards or status effects changed.
+pub fn stat_recompute_system(
+    cards: Res<Assets<Card>>,
+    mut players: Query<
+        (
+            &BaseStats,
+            &Inventory,
+            &StatusEffects,
+            &mut StatModifiers,
+            &mut Stats,
+        ),
+        Or<(
+            Changed<BaseStats>,
+            Changed<Inventory>,
+            Changed<StatusEffects>,
+        )>,
+    >,
+) {
+    for (base, inventory, effects, mut modifiers, mut stats) in players.iter_mut() {
+        recompute_stats(base, inventory, effects, &cards, &mut modifiers, &mut stats);
     }
 }
 
-pub fn update_cooldowns(time: Res<Time>, mut query: Query<&mut Stats>) {
-    for mut stats in query.iter_mut() {
+/// Counts down the cast and dash cooldowns, which hold while the player is stunned or frozen,
+/// and the invulnerability window.
+pub fn update_cooldowns(
+    time: Res<FixedTime>,
+    mut query: Query<(&mut Stats, &mut Health, &StatusEffects)>,
+) {
+    let dt = time.period.as_secs_f32();
+    for (mut stats, mut health, effects) in query.iter_mut() {
+        if health.invulnerable_timer > 0.0 {
+            health.invulnerable_timer -= dt;
+        }
+        if effects.is_incapacitated() {
+            continue;
+        }
         if stats.cooldown_timer > 0.0 {
-            stats.cooldown_timer -= time.delta_seconds();
+            stats.cooldown_timer -= dt;
+        }
+        if stats.dash_timer > 0.0 {
+            stats.dash_timer -= dt;
         }
     }
 }
@@ -230,145 +503,485 @@ pub fn projectile_cleanup(
     }
 }
 
+/// Despawns expired entities. Explosive projectiles go off where they are when they expire.
 pub fn lifetime_system(
     mut commands: Commands,
-    time: Res<Time>,
-    mut query: Query<(Entity, &mut Lifetime)>,
+    time: Res<FixedTime>,
+    mut query: Query<(
+        Entity,
+        &mut Lifetime,
+        &Transform,
+        Option<(&Projectile, &Explosive)>,
+    )>,
+    mut explosion_writer: EventWriter<Explosion>,
 ) {
-    for (entity, mut life) in query.iter_mut() {
-        life.time_left -= time.delta_seconds();
+    for (entity, mut life, transform, explosive) in query.iter_mut() {
+        life.time_left -= time.period.as_secs_f32();
         if life.time_left <= 0.0 {
+            if let Some((projectile, explosive)) = explosive {
+                explosion::detonate(
+                    &mut explosion_writer,
+                    projectile,
+                    explosive,
+                    transform.translation.truncate(),
+                    None,
+                );
+            }
             commands.entity(entity).despawn();
         }
     }
 }
 
-pub fn poison_damage_system(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut query: Query<(Entity, &mut Poisoned, &mut Health)>,
+/// Turns homing projectiles toward the closest player other than their owner, keeping their
+/// speed. Projectiles with nobody in range fly on unchanged.
+pub fn homing_system(
+    time: Res<FixedTime>,
+    players: Query<(&Player, &Transform)>,
+    mut projectiles: Query<(&Projectile, &Homing, &Transform, &mut Velocity)>,
+) {
+    let dt = time.period.as_secs_f32();
+    for (projectile, homing, transform, mut velocity) in projectiles.iter_mut() {
+        let pos = transform.translation.truncate();
+        let Some(target) = players
+            .iter()
+            .filter(|(player, _)| player.id != projectile.owner)
+            .map(|(_, t)| t.translation.truncate())
+            .filter(|t| t.distance(pos) <= homing.acquire_radius)
+            .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)))
+        else {
+            continue;
+        };
+        if velocity.linvel == Vec2::ZERO {
+            continue;
+        }
+        let current = velocity.linvel.y.atan2(velocity.linvel.x);
+        let wanted = (target - pos).y.atan2((target - pos).x);
+        let max_turn = homing.turn_rate * dt;
+        let turned = current + wrap_angle(wanted - current).clamp(-max_turn, max_turn);
+        velocity.linvel = Vec2::new(turned.cos(), turned.sin()) * velocity.linvel.length();
+    }
+}
+
+/// Heals player `id` by `amount`, capped at their maximum health, and reports what it restored.
+fn heal_player<'a>(
+    players: impl Iterator<Item = (&'a Player, Mut<'a, Health>)>,
+    id: usize,
+    amount: f32,
+    writer: &mut EventWriter<Healed>,
 ) {
-    for (entity, mut poison, mut health) in query.iter_mut() {
-        health.current -= poison.damage_per_second * time.delta_seconds();
-        poison.timer.tick(time.delta());
-        if poison.timer.finished() {
-            commands.entity(entity).remove::<Poisoned>();
+    for (player, mut health) in players {
+        if player.id != id || health.current <= 0.0 {
+            continue;
+        }
+        let restored = amount.min(health.max - health.current);
+        if restored > 0.0 {
+            health.current += restored;
+            writer.send(Healed {
+                player: id,
+                amount: restored,
+            });
         }
     }
 }
 
-pub fn slow_system(
+pub fn hit_stun_system(
     mut commands: Commands,
-    time: Res<Time>,
-    mut query: Query<(Entity, &mut Slowed)>,
+    time: Res<FixedTime>,
+    mut query: Query<(Entity, &mut HitStun)>,
 ) {
-    for (entity, mut slow) in query.iter_mut() {
-        slow.timer.tick(time.delta());
-        if slow.timer.finished() {
-            commands.entity(entity).remove::<Slowed>();
+    for (entity, mut stun) in query.iter_mut() {
+        stun.timer.tick(time.period);
+        if stun.timer.finished() {
+            commands.entity(entity).remove::<HitStun>();
         }
     }
 }
 
-fn aabb_collision(a_pos: Vec3, a_size: Vec2, b_pos: Vec3, b_size: Vec2) -> bool {
-    (a_pos.x - b_pos.x).abs() < (a_size.x + b_size.x) * 0.5
-        && (a_pos.y - b_pos.y).abs() < (a_size.y + b_size.y) * 0.5
+/// Pushes `entity` by `velocity` and takes away its horizontal control for a moment so the
+/// push is not cancelled straight away.
+fn knock_back(
+    commands: &mut Commands,
+    entity: Entity,
+    impulse: &mut ExternalImpulse,
+    mass: &ReadMassProperties,
+    velocity: Vec2,
+    stun: f32,
+) {
+    impulse.impulse += velocity * mass.0.mass;
+    commands.entity(entity).insert(HitStun {
+        timer: Timer::from_seconds(stun, TimerMode::Once),
+    });
 }
 
-pub fn projectile_player_collision(
+/// Handles projectiles touching the terrain. The physics step has already bounced them off the
+/// surface with their restitution; projectiles with bounces left keep going, the rest break,
+/// leave their hazards and report a [`ProjectileImpact`].
+pub fn projectile_terrain_collision(
     mut commands: Commands,
-    mut players: Query<(Entity, &Player, &mut Health, &Transform)>,
+    mut collisions: EventReader<CollisionEvent>,
+    terrain: Query<(), With<Terrain>>,
     mut projectiles: Query<(
-        Entity,
         &Projectile,
         &Transform,
-        Option<&PoisonEffect>,
-        Option<&SlowEffect>,
+        Option<&mut Bounces>,
+        Option<&Explosive>,
+        Option<&ImpactHazards>,
     )>,
-    mut kill_writer: EventWriter<PlayerKilled>,
+    mut existing_hazards: Query<(&mut Hazard, &mut Lifetime, &Transform)>,
+    mut impact_writer: EventWriter<ProjectileImpact>,
+    mut explosion_writer: EventWriter<Explosion>,
 ) {
-    let player_size = Vec2::splat(30.0);
-    let proj_size = Vec2::splat(10.0);
-    for (proj_entity, projectile, proj_transform, poison, slow) in projectiles.iter_mut() {
-        for (_player_entity, player, mut health, player_transform) in players.iter_mut() {
-            if player.id == projectile.owner {
+    let mut spent = Vec::new();
+    for event in collisions.iter() {
+        let CollisionEvent::Started(a, b, _) = *event else {
+            continue;
+        };
+        let proj_entity = if terrain.contains(b) {
+            a
+        } else if terrain.contains(a) {
+            b
+        } else {
+            continue;
+        };
+        if spent.contains(&proj_entity) {
+            continue;
+        }
+        let Ok((projectile, transform, bounces, explosive, hazards)) =
+            projectiles.get_mut(proj_entity)
+        else {
+            continue;
+        };
+        if let Some(mut bounces) = bounces {
+            if bounces.remaining > 0 {
+                bounces.remaining -= 1;
                 continue;
             }
-            if aabb_collision(
-                proj_transform.translation,
-                proj_size,
-                player_transform.translation,
-                player_size,
-            ) {
-                health.current -= projectile.damage;
-                if let Some(poison) = poison {
-                    commands.entity(_player_entity).insert(Poisoned {
-                        damage_per_second: poison.damage_per_second,
-                        timer: Timer::from_seconds(poison.duration, TimerMode::Once),
-                    });
-                }
-                if let Some(slow) = slow {
-                    commands.entity(_player_entity).insert(Slowed {
-                        amount: slow.amount,
-                        timer: Timer::from_seconds(slow.duration, TimerMode::Once),
-                    });
-                }
+        }
+        let position = transform.translation.truncate();
+        impact_writer.send(ProjectileImpact {
+            owner: projectile.owner,
+            position,
+        });
+        if let Some(explosive) = explosive {
+            explosion::detonate(&mut explosion_writer, projectile, explosive, position, None);
+        }
+        if let Some(hazards) = hazards {
+            hazard::spawn_hazards(
+                &mut commands,
+                &mut existing_hazards,
+                projectile.owner,
+                &hazards.0,
+                position,
+            );
+        }
+        commands.entity(proj_entity).despawn();
+        spent.push(proj_entity);
+    }
+}
+
+/// Applies projectile hits reported by Rapier. Projectiles never collide with their owner
+/// (see [`physics::projectile_groups`]) and use CCD, so fast shots cannot tunnel through a
+/// player between two ticks. Piercing projectiles survive a hit or a block while they have
+/// pierces left, losing some damage each time a player takes the hit. A projectile that breaks
+/// on a player leaves its hazards there.
+pub fn projectile_player_collision(
+    mut commands: Commands,
+    balance: Res<Balance>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut players: Query<(
+        &Player,
+        &mut Health,
+        &mut StatusEffects,
+        &Stats,
+        &mut ExternalImpulse,
+        &ReadMassProperties,
+        Has<Blocking>,
+    )>,
+    mut projectiles: Query<(
+        &mut Projectile,
+        &Transform,
+        &mut Velocity,
+        &mut CollisionGroups,
+        Option<&OnHitEffects>,
+        Option<&mut Pierce>,
+        Option<&Explosive>,
+        Option<&ImpactHazards>,
+    )>,
+    mut existing_hazards: Query<(&mut Hazard, &mut Lifetime, &Transform)>,
+    mut kill_writer: EventWriter<PlayerKilled>,
+    mut block_writer: EventWriter<Blocked>,
+    mut explosion_writer: EventWriter<Explosion>,
+    mut heal_writer: EventWriter<Healed>,
+) {
+    let mut spent = Vec::new();
+    let mut heals = Vec::new();
+    for event in collisions.iter() {
+        let CollisionEvent::Started(a, b, _) = *event else {
+            continue;
+        };
+        let (proj_entity, player_entity) = if projectiles.contains(a) && players.contains(b) {
+            (a, b)
+        } else if projectiles.contains(b) && players.contains(a) {
+            (b, a)
+        } else {
+            continue;
+        };
+        if spent.contains(&proj_entity) {
+            continue;
+        }
+        let (
+            Ok((
+                mut projectile,
+                proj_transform,
+                mut velocity,
+                mut groups,
+                on_hit,
+                mut pierce,
+                explosive,
+                hazards,
+            )),
+            Ok((player, mut health, mut effects, stats, mut impulse, mass, blocking)),
+        ) = (
+            projectiles.get_mut(proj_entity),
+            players.get_mut(player_entity),
+        )
+        else {
+            continue;
+        };
+        if player.id == projectile.owner || health.is_invulnerable() {
+            continue;
+        }
+        if pierce
+            .as_ref()
+            .is_some_and(|pierce| pierce.already_hit.contains(&player_entity))
+        {
+            continue;
+        }
+        if blocking {
+            let reflected = stats.reflect > 0.0;
+            block_writer.send(Blocked {
+                player: player.id,
+                attacker: projectile.owner,
+                position: proj_transform.translation.truncate(),
+                reflected,
+            });
+            if reflected {
+                // Sent straight back and now hurts whoever fired it.
+                projectile.owner = player.id;
+                *groups = physics::projectile_groups(player.id);
+                velocity.linvel = -velocity.linvel;
+            } else if !pierce_through(pierce.as_deref_mut(), player_entity) {
                 commands.entity(proj_entity).despawn();
-                if health.current <= 0.0 {
-                    kill_writer.send(PlayerKilled {
-                        winner: projectile.owner,
-                        loser: player.id,
-                    });
-                }
-                break;
+                spent.push(proj_entity);
+            }
+            continue;
+        }
+
+        let was_alive = health.current > 0.0;
+        if projectile.lifesteal > 0.0 {
+            heals.push((
+                projectile.owner,
+                projectile.damage.min(health.current.max(0.0)) * projectile.lifesteal,
+            ));
+        }
+        health.current -= projectile.damage;
+        if projectile.knockback > 0.0 {
+            knock_back(
+                &mut commands,
+                player_entity,
+                &mut impulse,
+                mass,
+                velocity.linvel.normalize_or_zero() * projectile.knockback,
+                balance.hit_stun,
+            );
+        }
+        for spec in on_hit.iter().flat_map(|on_hit| &on_hit.0) {
+            let lifesteal = if spec.kind.deals_damage() {
+                projectile.lifesteal * balance.poison_lifesteal
+            } else {
+                0.0
+            };
+            effects.apply(spec, projectile.owner, lifesteal, &balance.crowd_control);
+        }
+        if pierce_through(pierce.as_deref_mut(), player_entity) {
+            projectile.damage *= balance.pierce_falloff;
+        } else {
+            if let Some(explosive) = explosive {
+                explosion::detonate(
+                    &mut explosion_writer,
+                    &projectile,
+                    explosive,
+                    proj_transform.translation.truncate(),
+                    Some(player.id),
+                );
+            }
+            if let Some(hazards) = hazards {
+                hazard::spawn_hazards(
+                    &mut commands,
+                    &mut existing_hazards,
+                    projectile.owner,
+                    &hazards.0,
+                    proj_transform.translation.truncate(),
+                );
             }
+            commands.entity(proj_entity).despawn();
+            spent.push(proj_entity);
         }
+        if was_alive && health.current <= 0.0 {
+            kill_writer.send(PlayerKilled {
+                winner: projectile.owner,
+                loser: player.id,
+            });
+        }
+    }
+    for (id, amount) in heals {
+        heal_player(
+            players
+                .iter_mut()
+                .map(|(player, health, ..)| (player, health)),
+            id,
+            amount,
+            &mut heal_writer,
+        );
     }
 }
 
+/// Uses up one pierce to carry on past `target`. Returns false when the projectile should break.
+fn pierce_through(pierce: Option<&mut Pierce>, target: Entity) -> bool {
+    match pierce {
+        Some(pierce) if pierce.remaining > 0 => {
+            pierce.remaining -= 1;
+            pierce.already_hit.push(target);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Players this far below the ground have fallen out of the arena.
+const FALL_LIMIT: f32 = -300.0;
+
+/// Ends the round for players knocked off the edge of the arena, in favour of their opponent.
+pub fn fall_out_system(
+    mut players: Query<(&Player, &Transform, &mut Health)>,
+    mut kill_writer: EventWriter<PlayerKilled>,
+) {
+    let ids: Vec<usize> = players.iter().map(|(player, ..)| player.id).collect();
+    for (player, transform, mut health) in players.iter_mut() {
+        if transform.translation.y < FALL_LIMIT && health.current > 0.0 {
+            health.current = 0.0;
+            kill_writer.send(PlayerKilled {
+                winner: opponent(&ids, player.id),
+                loser: player.id,
+            });
+        }
+    }
+}
+
+/// Some player other than `id`, for rounds lost without anyone landing the final blow.
+fn opponent(ids: &[usize], id: usize) -> usize {
+    ids.iter().copied().find(|&other| other != id).unwrap_or(id)
+}
+
 pub fn round_manager(
     mut commands: Commands,
     mut manager: ResMut<RoundManager>,
     mut selection: ResMut<CardSelection>,
     mut reader: EventReader<PlayerKilled>,
-    mut players: Query<(&Player, &mut Health, &mut Transform)>,
-    projectiles: Query<Entity, With<Projectile>>,
+    mut players: Query<(
+        Entity,
+        &Player,
+        &mut Health,
+        &mut StatusEffects,
+        &mut Transform,
+        (&mut Velocity, &mut ExternalImpulse),
+        &Inventory,
+        Option<&Blocking>,
+    )>,
+    projectiles: Query<Entity, Or<(With<Projectile>, With<Hazard>)>>,
     mut next_state: ResMut<NextState<GameState>>,
+    balance: Res<Balance>,
+    library: Res<CardLibrary>,
+    cards: Res<Assets<Card>>,
 ) {
-    for event in reader.iter() {
-        match event.winner {
-            1 => manager.p1_score += 1,
-            2 => manager.p2_score += 1,
-            _ => {}
-        }
+    // Only the first kill of a tick ends the round; later ones (a poison tick landing on the
+    // same frame as a hit, say) belong to a round that is already over.
+    let Some(&PlayerKilled { winner, loser }) = reader.iter().next() else {
+        return;
+    };
+    reader.clear();
+    match winner {
+        1 => manager.p1_score += 1,
+        2 => manager.p2_score += 1,
+        _ => {}
+    }
 
-        for entity in &projectiles {
-            commands.entity(entity).despawn();
-        }
+    for entity in &projectiles {
+        commands.entity(entity).despawn();
+    }
 
-        for (player, mut health, mut transform) in players.iter_mut() {
-            health.current = health.max;
-            transform.translation = if player.id == 1 {
-                Vec3::new(-100.0, 0.0, 0.0)
-            } else {
-                Vec3::new(100.0, 0.0, 0.0)
-            };
+    for (
+        entity,
+        player,
+        mut health,
+        mut effects,
+        mut transform,
+        (mut velocity, mut impulse),
+        _,
+        blocking,
+    ) in players.iter_mut()
+    {
+        if let Some(blocking) = blocking {
+            commands.entity(blocking.shield).despawn_recursive();
         }
+        commands
+            .entity(entity)
+            .remove::<(HitStun, Dashing, Burst, Blocking)>();
+        *effects = StatusEffects::default();
+        health.current = health.max;
+        transform.translation = if player.id == 1 {
+            Vec3::new(-100.0, 0.0, 0.0)
+        } else {
+            Vec3::new(100.0, 0.0, 0.0)
+        };
+        // Knockback still in flight would otherwise carry over into the next round.
+        *velocity = Velocity::zero();
+        *impulse = ExternalImpulse::default();
+    }
 
-        info!("Scores - P1: {} P2: {}", manager.p1_score, manager.p2_score);
+    info!("Scores - P1: {} P2: {}", manager.p1_score, manager.p2_score);
 
-        if manager.p1_score >= manager.rounds_to_win || manager.p2_score >= manager.rounds_to_win {
-            info!("Game Over");
-            next_state.set(GameState::GameOver);
-        } else {
-            selection.loser = Some(event.loser);
-            selection.choices = crate::cards::random_choices(3);
-            info!("Player {} choose a card:", event.loser);
-            for (i, c) in selection.choices.iter().enumerate() {
-                info!("{}: {} - {}", i + 1, c.name, c.description);
-            }
-            next_state.set(GameState::CardSelection);
+    if manager.p1_score >= manager.rounds_to_win || manager.p2_score >= manager.rounds_to_win {
+        info!("Game Over");
+        next_state.set(GameState::GameOver);
+    } else {
+        // The round's loser can still be ahead in the match.
+        let leading = match loser {
+            1 => manager.p1_score > manager.p2_score,
+            _ => manager.p2_score > manager.p1_score,
+        };
+        let default_inventory = Inventory::default();
+        let inventory = players
+            .iter()
+            .find(|(_, player, ..)| player.id == loser)
+            .map_or(&default_inventory, |(.., inventory, _)| inventory);
+        selection.choices =
+            cards::random_choices(3, &library, &cards, &balance.card_draw, inventory, leading);
+        if selection.choices.is_empty() {
+            warn!("No cards can be offered to player {loser}, skipping card selection");
+            return;
         }
+        selection.loser = Some(loser);
+        info!("Player {} choose a card:", loser);
+        for (i, c) in selection
+            .choices
+            .iter()
+            .filter_map(|h| cards.get(h))
+            .enumerate()
+        {
+            info!("{}: {} ({:?}) - {}", i + 1, c.name, c.rarity, c.description);
+        }
+        next_state.set(GameState::CardSelection);
     }
 }
 
@@ -376,9 +989,18 @@ fn spawn_projectile(
     commands: &mut Commands,
     owner: usize,
     stats: &Stats,
+    angle: f32,
     transform: &Transform,
     assets: &GameAssets,
+    balance: &Balance,
 ) {
+    let bounces = stats.bounces.floor() as u32;
+    let kind = if bounces > 0 {
+        ProjectileKind::Bouncer
+    } else {
+        ProjectileKind::Bolt
+    };
+    let tuning = balance.projectile_physics(kind);
     let mut entity = commands.spawn((
         SpriteBundle {
             texture: assets.projectile.clone(),
@@ -387,46 +1009,77 @@ fn spawn_projectile(
                 ..default()
             },
             transform: Transform::from_translation(
-                transform.translation
-                    + Vec3::new(stats.aim_angle.cos(), stats.aim_angle.sin(), 0.0) * 10.0,
+                transform.translation + Vec3::new(angle.cos(), angle.sin(), 0.0) * 10.0,
             ),
             ..default()
         },
         Projectile {
             owner,
             damage: stats.damage,
+            knockback: stats.knockback,
+            lifesteal: stats.lifesteal,
+        },
+        Lifetime {
+            time_left: balance.projectile_lifetime,
         },
-        Lifetime { time_left: 2.0 },
         RigidBody::Dynamic,
         Collider::ball(5.0),
-        Velocity::linear(
-            Vec2::new(stats.aim_angle.cos(), stats.aim_angle.sin()) * stats.projectile_speed,
-        ),
+        physics::projectile_groups(owner),
+        physics::projectile_solver_groups(),
+        ActiveEvents::COLLISION_EVENTS,
+        Ccd::enabled(),
+        // Max/Min so the terrain's own material does not water the tuning down.
+        Restitution {
+            coefficient: tuning.restitution,
+            combine_rule: CoefficientCombineRule::Max,
+        },
+        Friction {
+            coefficient: 0.0,
+            combine_rule: CoefficientCombineRule::Min,
+        },
+        GravityScale(tuning.gravity_scale),
+        Velocity::linear(Vec2::new(angle.cos(), angle.sin()) * stats.projectile_speed),
     ));
-    if stats.poison_damage > 0.0 {
-        entity.insert(PoisonEffect {
-            damage_per_second: stats.poison_damage,
-            duration: 3.0,
+    if bounces > 0 {
+        entity.insert(Bounces { remaining: bounces });
+    }
+    if stats.explosion_radius > 0.0 {
+        entity.insert(Explosive {
+            radius: stats.explosion_radius,
+            falloff: balance.explosion.falloff,
         });
     }
-    if stats.slow_amount > 0.0 {
-        entity.insert(SlowEffect {
-            amount: stats.slow_amount,
-            duration: 2.0,
+    let pierce = stats.pierce.floor() as u32;
+    if pierce > 0 {
+        entity.insert(Pierce {
+            remaining: pierce,
+            already_hit: Vec::new(),
         });
     }
+    if stats.homing_turn_rate > 0.0 && stats.homing_radius > 0.0 {
+        entity.insert(Homing {
+            turn_rate: stats.homing_turn_rate.to_radians(),
+            acquire_radius: stats.homing_radius,
+        });
+    }
+    if !stats.on_hit.is_empty() {
+        entity.insert(OnHitEffects(stats.on_hit.clone()));
+    }
+    if !stats.on_impact.is_empty() {
+        entity.insert(ImpactHazards(stats.on_impact.clone()));
+    }
 }
 
 pub fn card_input_system(
     keyboard: Res<Input<KeyCode>>,
     mut next_state: ResMut<NextState<GameState>>,
     mut selection: ResMut<CardSelection>,
-    mut players: Query<(&Player, &mut Stats, &mut Inventory)>,
+    mut players: Query<(&Player, &mut Inventory)>,
+    cards: Res<Assets<Card>>,
 ) {
-    let loser = match selection.loser {
-        Some(id) => id,
-        None => return,
-    };
+    if selection.loser.is_none() {
+        return;
+    }
     let mut picked = None;
     if keyboard.just_pressed(KeyCode::Key1) {
         picked = Some(0);
@@ -436,16 +1089,6 @@ pub fn card_input_system(
         picked = Some(2);
     }
     if let Some(idx) = picked {
-        if let Some(card) = selection.choices.get(idx) {
-            for (player, mut stats, mut inv) in players.iter_mut() {
-                if player.id == loser {
-                    crate::cards::apply(card.id, &mut stats);
-                    inv.cards.push(card.id);
-                }
-            }
-        }
-        selection.loser = None;
-        selection.choices.clear();
-        next_state.set(GameState::InGame);
+        pick_card(idx, &mut selection, &mut players, &cards, &mut next_state);
     }
 }
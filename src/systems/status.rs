@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+use crate::components::{Health, Player};
+use crate::events::{Healed, PlayerKilled};
+use crate::status::{StatusEffects, StatusKind};
+
+use super::heal_player;
+
+/// Runs every status effect for one tick: damage over time and freezes shattering, crediting
+/// kills and lifesteal to whoever applied them, then drops the effects that ran out.
+pub fn status_effect_system(
+    time: Res<FixedTime>,
+    mut players: Query<(&Player, &mut StatusEffects, &mut Health)>,
+    mut kill_writer: EventWriter<PlayerKilled>,
+    mut heal_writer: EventWriter<Healed>,
+) {
+    let dt = time.period.as_secs_f32();
+    let mut heals = Vec::new();
+    for (player, mut tracked, mut health) in players.iter_mut() {
+        // Counting effects down leaves the stats they give alone, so only an effect running
+        // out tells `stat_recompute_system` to rebuild them.
+        let effects = tracked.bypass_change_detection();
+        for effect in effects.active.iter_mut() {
+            let shatters = effect.kind == StatusKind::Freeze && effect.time_left <= dt;
+            if effect.kind.deals_damage() || shatters {
+                let was_alive = health.current > 0.0;
+                let damage = if shatters {
+                    effect.magnitude
+                } else {
+                    effect.magnitude * dt
+                };
+                if effect.lifesteal > 0.0 {
+                    heals.push((
+                        effect.source,
+                        damage.min(health.current.max(0.0)) * effect.lifesteal,
+                    ));
+                }
+                health.current -= damage;
+                if was_alive && health.current <= 0.0 {
+                    kill_writer.send(PlayerKilled {
+                        winner: effect.source,
+                        loser: player.id,
+                    });
+                }
+            }
+            effect.time_left -= dt;
+        }
+        let running = effects.active.len();
+        effects.active.retain(|effect| effect.time_left > 0.0);
+        let expired = effects.active.len() < running;
+        effects.tick(dt);
+        if expired {
+            tracked.set_changed();
+        }
+    }
+    for (id, amount) in heals {
+        heal_player(
+            players
+                .iter_mut()
+                .map(|(player, _, health)| (player, health)),
+            id,
+            amount,
+            &mut heal_writer,
+        );
+    }
+}